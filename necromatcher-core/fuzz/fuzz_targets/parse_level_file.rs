@@ -0,0 +1,13 @@
+//! Run with `cargo +nightly fuzz run parse_level_file` from `necromatcher-core`.
+//! Bad level files should be errors rather than panics.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use necromatcher_core::{state::parse_level_file, COLS, ROWS};
+
+fuzz_target!(|data: &str| {
+    if let Ok(level) = parse_level_file(data) {
+        assert_eq!(level.pieces.len(), ROWS * COLS);
+    }
+});
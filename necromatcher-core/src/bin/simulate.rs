@@ -0,0 +1,242 @@
+//! Plays a level many times with each strategy and prints a table of how it went,
+//! for balancing the starting souls in level files. Run with `--help` for the options.
+
+use std::{env, fs, process::ExitCode};
+
+use chrono::NaiveDate;
+use necromatcher_core::{
+    event::GameEvent,
+    state::{
+        game_event_handler::StateEventHandler,
+        level_generator::endless_board_size,
+        parse_level_file,
+        simulation::{simulate, Outcome, SimulationReport, Strategy},
+        GameState, LevelFailure, PieceType,
+    },
+};
+
+const USAGE: &str = "\
+Usage: simulate [--level N | --file PATH | --endless SEED | --daily YYYY-MM-DD]
+                [--strategy NAMES] [--runs N] [--seed N] [--souls B,H,S]
+
+  --level N       simulate campaign level N, from 1 (the default)
+  --file PATH     simulate a level file, e.g. an edited copy of levels/tutorial.txt
+  --endless SEED  simulate the first endless level generated from the seed
+  --daily DATE    simulate the daily challenge for the date
+  --strategy      random, greedy or solver, or a comma separated list (all by default)
+  --runs N        how many times each strategy plays the level (1000 by default)
+  --seed N        the seed for the first run, each run after adds one (0 by default)
+  --souls B,H,S   start with these bowmen, hounds and swordsmen instead, in the
+                  same order as the souls line of a level file";
+
+/// Where the level comes from
+enum Level {
+    Event(GameEvent),
+    File(String),
+}
+
+struct Options {
+    level: Level,
+    strategies: Vec<Strategy>,
+    runs: usize,
+    seed: u64,
+    souls: Option<(usize, usize, usize)>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        level: Level::Event(GameEvent::LoadLevel { level_id: 0 }),
+        strategies: Strategy::ALL.to_vec(),
+        runs: 1000,
+        seed: 0,
+        souls: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+
+        match arg.as_str() {
+            "--level" => {
+                let level = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|level| *level > 0)
+                    .ok_or("--level needs a level number, starting from 1")?;
+                options.level = Level::Event(GameEvent::LoadLevel {
+                    level_id: level - 1,
+                });
+            }
+            "--file" => options.level = Level::File(value),
+            "--endless" => {
+                let seed = value.parse().map_err(|_| format!("Bad seed {value}"))?;
+                let difficulty = 1;
+                let (width, height) = endless_board_size(difficulty);
+                options.level = Level::Event(GameEvent::GenerateLevel {
+                    seed,
+                    width,
+                    height,
+                    difficulty,
+                });
+            }
+            "--daily" => {
+                let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                    .map_err(|_| format!("Bad date {value}, expected YYYY-MM-DD"))?;
+                options.level = Level::Event(GameEvent::LoadDaily { date });
+            }
+            "--strategy" => {
+                options.strategies = value
+                    .split(',')
+                    .map(|name| {
+                        Strategy::from_name(name.trim())
+                            .ok_or_else(|| format!("Unknown strategy {name}"))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--runs" => {
+                options.runs = value
+                    .parse()
+                    .map_err(|_| format!("Bad number of runs {value}"))?;
+            }
+            "--seed" => options.seed = value.parse().map_err(|_| format!("Bad seed {value}"))?,
+            "--souls" => {
+                let souls = value
+                    .split(',')
+                    .map(|count| count.trim().parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>();
+                let Ok([bowmen, hounds, swordsmen]) = souls.as_deref() else {
+                    return Err(format!("Bad souls {value}, expected three numbers"));
+                };
+                // stored as triangles, squares and circles
+                options.souls = Some((*bowmen, *swordsmen, *hounds));
+            }
+            other => return Err(format!("Unknown option {other}")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn load(options: &Options) -> Result<GameState, String> {
+    let mut state = match &options.level {
+        Level::Event(event) => {
+            let mut state = GameState::default();
+            state
+                .apply_event(*event)
+                .map_err(|rejection| rejection.to_string())?;
+            state
+        }
+        Level::File(path) => {
+            let data =
+                fs::read_to_string(path).map_err(|err| format!("Can't read {path} - {err}"))?;
+            GameState::from_level_data(parse_level_file(&data).map_err(|err| err.to_string())?)
+        }
+    };
+
+    if let Some((triangles, squares, circles)) = options.souls {
+        state.num_triangles = triangles;
+        state.num_squares = squares;
+        state.num_circles = circles;
+    }
+
+    Ok(state)
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let result = parse_args(args).and_then(|options| {
+        let state = load(&options)?;
+
+        println!(
+            "{} runs per strategy, starting with {} bowmen, {} hounds and {} swordsmen\n",
+            options.runs, state.num_triangles, state.num_circles, state.num_squares
+        );
+        println!(
+            "{:<8} {:>6}  {:>6}  {:>6}  {:>10}  {:<15}  {:<13}  {:>8}  {:>5}  {:>8}  {:>7}",
+            "strategy",
+            "won",
+            "lost",
+            "drawn",
+            "placements",
+            "summoned b/h/s",
+            "left b/h/s",
+            "no souls",
+            "stuck",
+            "no moves",
+            "gave up"
+        );
+        for strategy in &options.strategies {
+            let report = simulate(&state, *strategy, options.runs, options.seed);
+            println!("{}", report_row(&report));
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// One line of the table, with the summoned and left columns in level file order
+fn report_row(report: &SimulationReport) -> String {
+    let per_type = |average: &dyn Fn(PieceType) -> f32| {
+        format!(
+            "{:.1}/{:.1}/{:.1}",
+            average(PieceType::Bowman),
+            average(PieceType::Hound),
+            average(PieceType::Swordsman)
+        )
+    };
+    let percent = |rate: f32| format!("{:.1}%", rate * 100.);
+    let failed = |reason| percent(report.outcome_rate(Outcome::Failed(reason)));
+
+    format!(
+        "{:<8} {:>6}  {:>6}  {:>6}  {:>10.1}  {:<15}  {:<13}  {:>8}  {:>5}  {:>8}  {:>7}",
+        report.strategy.name(),
+        percent(report.win_rate()),
+        percent(report.outcome_rate(Outcome::Lost)),
+        percent(report.outcome_rate(Outcome::Draw)),
+        report.average_placements(),
+        per_type(&|piece_type| report.average_summoned(piece_type)),
+        per_type(&|piece_type| report.average_left(piece_type)),
+        failed(LevelFailure::OutOfSouls),
+        failed(LevelFailure::NoValidPlacements),
+        failed(LevelFailure::OutOfMoves),
+        percent(report.outcome_rate(Outcome::GaveUp)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use necromatcher_core::{event::GameEvent, state::simulation::Strategy};
+
+    use super::{parse_args, Level};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args("--level 2 --strategy greedy,solver --souls 4,2,1")).unwrap();
+        assert!(matches!(
+            options.level,
+            Level::Event(GameEvent::LoadLevel { level_id: 1 })
+        ));
+        assert_eq!(options.strategies, vec![Strategy::Greedy, Strategy::Solver]);
+        assert_eq!(options.souls, Some((4, 1, 2)));
+
+        assert!(parse_args(args("--runs")).is_err());
+        assert!(parse_args(args("--strategy clever")).is_err());
+        assert!(parse_args(args("--souls 4,2")).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use super::state::{LevelFailure, PieceType};
+
+/// A game event that
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::event::Event))]
+pub enum GameEvent {
+    /// Resets the game
+    Reset,
+
+    /// Seeds the rng so we can have repeatable games
+    SeedRng { seed: u64 },
+
+    /// Loads a level from text file
+    LoadLevel { level_id: usize },
+
+    /// Generates a random level, the same seed always generates the same level
+    GenerateLevel {
+        seed: u64,
+        width: usize,
+        height: usize,
+        difficulty: usize,
+    },
+
+    /// Loads the daily challenge for the given date
+    LoadDaily { date: NaiveDate },
+
+    /// Loads the board for a two player versus game
+    LoadVersus,
+
+    /// Move to the next level
+    NextLevel,
+
+    /// Take back the last piece placed in the current level. This isn't logged,
+    /// instead the placement it takes back is removed from the log
+    Undo,
+
+    /// The current level's time limit ran out
+    TimeExpired,
+
+    /// A player places a piece on a map
+    PlacePlayerPiece {
+        x: usize,
+        y: usize,
+        piece_type: PieceType,
+    },
+}
+
+/// Why a [GameEvent] couldn't be applied to the game state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventRejection {
+    /// The player has no souls left of the piece type they tried to summon
+    NoCapacity,
+    /// There is already a piece on the tile
+    TileOccupied,
+    /// Pieces can only be summoned next to one of the player's own pieces
+    NoAdjacentFriend,
+    /// The tile isn't on the board
+    OutOfBounds,
+    /// The level has been lost, so nothing can be placed until it is reset or undone
+    LevelFailed(LevelFailure),
+    /// The requested level doesn't exist or can't be generated
+    InvalidLevel,
+    /// There is no level after this one
+    LastLevel,
+    /// The game mode only has one level
+    SingleLevel,
+    /// The level doesn't have a time limit
+    NoTimeLimit,
+    /// The level is already over
+    LevelFinished,
+    /// No pieces have been placed in this level
+    NothingToUndo,
+}
+
+impl fmt::Display for EventRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCapacity => write!(f, "You have no souls left to summon that piece"),
+            Self::TileOccupied => write!(f, "That tile is already taken"),
+            Self::NoAdjacentFriend => write!(f, "You can only summon next to your own pieces"),
+            Self::OutOfBounds => write!(f, "That isn't on the board"),
+            Self::LevelFailed(_) => write!(f, "The level has been lost"),
+            Self::InvalidLevel => write!(f, "That level doesn't exist"),
+            Self::LastLevel => write!(f, "This is the last level"),
+            Self::SingleLevel => write!(f, "This game only has one level"),
+            Self::NoTimeLimit => write!(f, "This level doesn't have a time limit"),
+            Self::LevelFinished => write!(f, "The level is already over"),
+            Self::NothingToUndo => write!(f, "There is nothing to undo"),
+        }
+    }
+}
+
+impl std::error::Error for EventRejection {}
@@ -0,0 +1,16 @@
+//! The rules engine for necromatcher. Holds the [GameState](state::GameState), applies
+//! [GameEvent](event::GameEvent)s to it and describes what changed as
+//! [SideEffect](state::side_effects::SideEffect)s, without knowing anything about how
+//! the game is drawn or played. The game, tools and tests all build on this crate.
+//!
+//! Turn on the `bevy` feature to use the state as a Bevy resource and the events as Bevy events.
+
+pub mod event;
+pub mod state;
+pub mod utils;
+
+/// Number of rows in the grid
+pub const ROWS: usize = 8;
+
+/// Number of columns in the grid
+pub const COLS: usize = 8;
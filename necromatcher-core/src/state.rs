@@ -0,0 +1,470 @@
+use std::ops::{Deref, DerefMut};
+
+use chrono::NaiveDate;
+
+use rand::{thread_rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use self::{board::Board, rules::LevelRules};
+
+use super::event::GameEvent;
+
+pub mod board;
+pub mod daily;
+pub mod game_event_handler;
+pub mod gravity;
+pub mod history;
+pub mod level_generator;
+mod level_loader;
+pub mod objectives;
+pub mod opponent;
+pub mod preview;
+#[cfg(test)]
+mod properties;
+pub mod random;
+pub mod rules;
+pub mod side_effects;
+pub mod simulation;
+pub mod solver;
+pub mod versus;
+
+pub use level_loader::{parse_level_file, LevelData, LevelParseError};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PieceType {
+    #[default]
+    Swordsman,
+    Hound,
+    Bowman,
+    Wall,
+}
+
+impl PieceType {
+    pub fn toggle(self) -> Self {
+        match self {
+            PieceType::Swordsman => PieceType::Hound,
+            PieceType::Hound => PieceType::Bowman,
+            PieceType::Bowman => PieceType::Swordsman,
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Piece {
+    /// No piece at all
+    Empty,
+    /// An obstacle which blocks matches and placements and for now can't be destroyed
+    Obstacle(PieceType),
+    /// A piece owned by the player
+    Player0(PieceType),
+    /// A piece owned by the computer
+    Player1(PieceType),
+}
+
+/// One of the two sides on the board. In single player games the player
+/// is always [Player::Player0] and [Player::Player1] is the computer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Player {
+    #[default]
+    Player0,
+    Player1,
+}
+
+impl Player {
+    pub fn other(self) -> Self {
+        match self {
+            Self::Player0 => Self::Player1,
+            Self::Player1 => Self::Player0,
+        }
+    }
+
+    /// A piece of the given type owned by this player
+    pub fn piece(self, piece_type: PieceType) -> Piece {
+        match self {
+            Self::Player0 => Piece::Player0(piece_type),
+            Self::Player1 => Piece::Player1(piece_type),
+        }
+    }
+
+    /// Returns true if the piece belongs to this player
+    pub fn owns(self, piece: Piece) -> bool {
+        matches!(
+            (self, piece),
+            (Self::Player0, Piece::Player0(_)) | (Self::Player1, Piece::Player1(_))
+        )
+    }
+
+    /// The player's name as shown to people, counting from one
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Player0 => "Player 1",
+            Self::Player1 => "Player 2",
+        }
+    }
+}
+
+/// Why a level was lost
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LevelFailure {
+    /// Used up the level's move budget without clearing it
+    OutOfMoves,
+    /// The level's time limit ran out
+    OutOfTime,
+    /// There are no souls left to summon with
+    OutOfSouls,
+    /// There are souls left but nowhere to summon them
+    NoValidPlacements,
+}
+
+/// Where the levels being played come from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// The hand written levels, played in order
+    #[default]
+    Campaign,
+    /// Generated levels that get harder each time one is cleared.
+    /// Stores the parameters used to generate the current level.
+    Endless {
+        seed: u64,
+        width: usize,
+        height: usize,
+        difficulty: usize,
+    },
+    /// A single generated level picked by the date, with par from the solver
+    /// if it could work it out
+    Daily { date: NaiveDate, par: Option<usize> },
+    /// Two people taking turns on the same board
+    Versus,
+}
+
+/// Everything about the game being played. The [Board] it wraps holds the pieces, souls
+/// and progress through the level, and its fields and methods can be used directly on the [GameState].
+#[derive(Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::system::Resource))]
+pub struct GameState {
+    pub board: Board,
+
+    pub level_message: String,
+
+    pub mode: GameMode,
+
+    /// Optional rules for the current level
+    pub rules: LevelRules,
+    /// Seconds spent playing the current level, only tracked if it has a time limit
+    level_elapsed: f32,
+    /// Set once the level's time limit runs out
+    time_expired: bool,
+
+    current_level: usize,
+
+    rng: ChaCha20Rng,
+    events: Vec<GameEvent>,
+}
+
+impl Deref for GameState {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        &self.board
+    }
+}
+
+impl DerefMut for GameState {
+    fn deref_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        let seed = thread_rng().next_u64();
+        let event = GameEvent::SeedRng { seed };
+        Self {
+            rng: ChaCha20Rng::seed_from_u64(seed),
+            events: vec![event],
+            board: Board::default(),
+            level_message: String::new(),
+            mode: GameMode::Campaign,
+            rules: LevelRules::default(),
+            level_elapsed: 0.,
+            time_expired: false,
+            current_level: 0,
+        }
+    }
+}
+
+impl GameState {
+    /// Returns true once the level's objective is complete,
+    /// by default when all red cells are defeated
+    pub fn is_level_over(&self) -> bool {
+        self.board.is_level_over(&self.rules)
+    }
+
+    /// Gets the current level
+    pub fn get_current_level(&self) -> usize {
+        self.current_level + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    Horizontal { start_idx: usize, length: usize },
+    Vertical { start_idx: usize, length: usize },
+}
+
+#[cfg(test)]
+mod test {
+    use crate::state::Match;
+
+    use super::{GameState, Piece, PieceType};
+
+    #[test]
+    fn test_matches_horizontal_at_any_index() {
+        let mut state = GameState::default();
+        state.pieces[1] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[2] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[3] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Horizontal {
+                start_idx: 1,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_horizontal_more_than_3() {
+        let mut state = GameState::default();
+        state.pieces[1] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[2] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[3] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[4] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[5] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Horizontal {
+                start_idx: 1,
+                length: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_horizontal_at_the_end_of_the_grid() {
+        let mut state = GameState::default();
+        state.pieces[60] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[61] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[62] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[63] = Piece::Player0(PieceType::Swordsman);
+
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Horizontal {
+                start_idx: 60,
+                length: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_vertical_at_the_end_of_the_grid() {
+        let mut state = GameState::default();
+        state.pieces[39] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[47] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[55] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[63] = Piece::Player0(PieceType::Swordsman);
+
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Vertical {
+                start_idx: 39,
+                length: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn test_doesnt_match_horizontal_over_row_boundary() {
+        let mut state = GameState::default();
+        state.pieces[6] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[7] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[8] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(state.get_matches(), vec![]);
+    }
+
+    #[test]
+    fn test_matches_horizontal_at_row_start() {
+        let mut state = GameState::default();
+        state.pieces[8] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[9] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[10] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Horizontal {
+                start_idx: 8,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_horizontal_at_row_end() {
+        let mut state = GameState::default();
+        for idx in 5..=7 {
+            state.pieces[idx] = Piece::Player0(PieceType::Hound);
+        }
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Horizontal {
+                start_idx: 5,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multiple_horizontal_matches() {
+        let mut state = GameState::default();
+        for idx in 4..=10 {
+            state.pieces[idx] = Piece::Player0(PieceType::Bowman);
+        }
+
+        assert_eq!(
+            state.get_matches(),
+            vec![
+                Match::Horizontal {
+                    start_idx: 4,
+                    length: 4
+                },
+                Match::Horizontal {
+                    start_idx: 8,
+                    length: 3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_horizontal_and_vertical_matches() {
+        let mut state = GameState::default();
+        for idx in [4, 5, 6, 13, 21] {
+            state.pieces[idx] = Piece::Player0(PieceType::Bowman);
+        }
+
+        assert_eq!(
+            state.get_matches(),
+            vec![
+                Match::Horizontal {
+                    start_idx: 4,
+                    length: 3
+                },
+                Match::Vertical {
+                    start_idx: 5,
+                    length: 3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_vertical_at_any_index() {
+        let mut state = GameState::default();
+        state.pieces[17] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[25] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[33] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Vertical {
+                start_idx: 17,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_vertical_more_than_3() {
+        let mut state = GameState::default();
+        state.pieces[9] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[17] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[25] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[33] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[41] = Piece::Player0(PieceType::Swordsman);
+
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Vertical {
+                start_idx: 9,
+                length: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_doesnt_match_vertical_over_column_boundary() {
+        let mut state = GameState::default();
+        state.pieces[52] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[60] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[61] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(state.get_matches(), vec![]);
+
+        let mut state = GameState::default();
+        state.pieces[48] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[56] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[1] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(state.get_matches(), vec![]);
+    }
+
+    #[test]
+    fn test_matches_vertical_at_col_start() {
+        let mut state = GameState::default();
+        state.pieces[1] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[9] = Piece::Player0(PieceType::Swordsman);
+        state.pieces[17] = Piece::Player0(PieceType::Swordsman);
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Vertical {
+                start_idx: 1,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matches_vertical_at_col_end() {
+        let mut state = GameState::default();
+        for idx in [46, 54, 62] {
+            state.pieces[idx] = Piece::Player0(PieceType::Hound);
+        }
+        assert_eq!(
+            state.get_matches(),
+            vec![Match::Vertical {
+                start_idx: 46,
+                length: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multiple_vertical_matches() {
+        let mut state = GameState::default();
+        for idx in [22, 30, 38, 46, 17, 25, 33] {
+            state.pieces[idx] = Piece::Player0(PieceType::Bowman);
+        }
+
+        assert_eq!(
+            state.get_matches(),
+            vec![
+                Match::Vertical {
+                    start_idx: 17,
+                    length: 3
+                },
+                Match::Vertical {
+                    start_idx: 22,
+                    length: 4
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! The pieces on the board and the souls each player has left to summon with.
+//!
+//! This is everything that decides which placements can be made and what they do,
+//! kept apart from the rest of the [GameState](super::GameState) so it is small to copy
+//! and can be hashed. Solvers and computer opponents can explore thousands of positions
+//! by copying it and placing pieces with the level's [LevelRules], and use it as the
+//! key in a transposition table to skip positions they have already seen.
+
+use crate::{event::EventRejection, utils::tile_to_idx, COLS, ROWS};
+
+use super::{
+    game_event_handler::DEFAULT_DESPAWN_DELAY, rules::LevelRules, side_effects::SideEffect, Match,
+    Piece, PieceType, Player,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Board {
+    /// The piece on each tile, indexed with [tile_to_idx]
+    pub pieces: [Piece; 64],
+
+    pub num_triangles: usize,
+    pub num_squares: usize,
+    pub num_circles: usize,
+
+    /// Whose turn it is. The soul counts above always belong to this player.
+    pub current_player: Player,
+    /// The triangles, squares and circles of the player waiting for their turn in versus games
+    pub waiting_souls: (usize, usize, usize),
+
+    /// How many pieces have been placed in the current level
+    pub placements: usize,
+    /// How many red souls have been harvested in the current level
+    pub souls_harvested: usize,
+    /// How many pieces from the refill queue have dropped in so far
+    pub refills_used: usize,
+    /// How many turns the red souls have taken, one each time the spawn rule is due
+    pub enemy_turns: usize,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self {
+            pieces: [Piece::Empty; 64],
+            num_triangles: 0,
+            num_squares: 0,
+            num_circles: 0,
+            current_player: Player::Player0,
+            waiting_souls: (0, 0, 0),
+            placements: 0,
+            souls_harvested: 0,
+            refills_used: 0,
+            enemy_turns: 0,
+        }
+    }
+}
+
+impl Board {
+    /// Places a piece for the current player and resolves any matches it makes,
+    /// following the level's rules. Doesn't validate the placement.
+    pub fn place_piece(
+        &mut self,
+        x: usize,
+        y: usize,
+        piece_type: PieceType,
+        rules: &LevelRules,
+    ) -> Vec<SideEffect> {
+        // remove the required piece from the player state
+        match piece_type {
+            PieceType::Swordsman => {
+                self.num_squares -= 1;
+            }
+            PieceType::Hound => {
+                self.num_circles -= 1;
+            }
+            PieceType::Bowman => {
+                self.num_triangles -= 1;
+            }
+            PieceType::Wall => {
+                // nop
+            }
+        }
+
+        // place the piece
+        let placed_idx = tile_to_idx(x, y);
+        self.pieces[placed_idx] = self.current_player.piece(piece_type);
+        self.placements += 1;
+        let is_player_owned = self.current_player == Player::Player0;
+        let mut side_effects = vec![SideEffect::SpawnAtTile {
+            idx: placed_idx,
+            piece_type,
+            is_player_owned,
+            also_destroy: false,
+        }];
+
+        if rules.gravity {
+            side_effects.extend(self.settle_board(rules));
+            return side_effects;
+        }
+
+        // find any matches and remove them
+        for idx in self.clear_matches() {
+            if idx == placed_idx {
+                // replace the first element with a spawn+despawn
+                side_effects[0] = SideEffect::SpawnAtTile {
+                    idx: placed_idx,
+                    piece_type,
+                    is_player_owned,
+                    also_destroy: true,
+                }
+            } else {
+                // destroy other elements
+                side_effects.push(SideEffect::DespawnAtTile {
+                    idx,
+                    delay: DEFAULT_DESPAWN_DELAY,
+                });
+            }
+        }
+
+        side_effects
+    }
+
+    /// Removes every match on the board, adding any opponent souls that were
+    /// harvested to the current player's capacity. The current player's own
+    /// pieces in a match are used up. Returns the cleared tile indexes.
+    pub(super) fn clear_matches(&mut self) -> Vec<usize> {
+        let mut cleared = vec![];
+
+        for matched in self.get_matches() {
+            let idxs_that_matched = match matched {
+                Match::Horizontal { start_idx, length } => {
+                    (start_idx..start_idx + length).collect::<Vec<_>>()
+                }
+                Match::Vertical { start_idx, length } => (0..length)
+                    .map(|step| start_idx + step * COLS)
+                    .collect::<Vec<_>>(),
+            };
+
+            for idx in idxs_that_matched {
+                // horizontal and vertical matches can share a tile
+                if self.pieces[idx] == Piece::Empty {
+                    continue;
+                }
+
+                // if we removed an opponent's element, add it to capacity
+                let piece = self.pieces[idx];
+                if let (true, Piece::Player0(pt) | Piece::Player1(pt)) =
+                    (self.current_player.other().owns(piece), piece)
+                {
+                    self.souls_harvested += 1;
+
+                    match pt {
+                        PieceType::Swordsman => self.num_squares += 1,
+                        PieceType::Hound => self.num_circles += 1,
+                        PieceType::Bowman => self.num_triangles += 1,
+                        PieceType::Wall => {
+                            //nop, how is this even possible?
+                        }
+                    }
+                }
+
+                self.pieces[idx] = Piece::Empty;
+                cleared.push(idx);
+            }
+        }
+
+        cleared
+    }
+
+    /// Returns true once the level's objective is complete
+    pub fn is_level_over(&self, rules: &LevelRules) -> bool {
+        rules.objective.as_objective().is_complete(self)
+    }
+
+    /// Returns true if a piece of the given type can be placed
+    pub fn has_capacity(&self, piece_type: PieceType) -> bool {
+        (match piece_type {
+            PieceType::Swordsman => self.num_squares,
+            PieceType::Hound => self.num_circles,
+            PieceType::Bowman => self.num_triangles,
+            _ => 0,
+        }) > 0
+    }
+
+    /// Returns true if the x/y position passed is a valid location to place a player piece.
+    /// See [Board::check_placement] for the conditions.
+    pub fn is_valid_placement_position(&self, selected_x: usize, selected_y: usize) -> bool {
+        self.check_placement(selected_x, selected_y).is_ok()
+    }
+
+    /// Checks the x/y position passed is a valid location to place a player piece.
+    ///
+    /// A valid piece must meet these conditions:
+    ///  (a) has a tile under the cursor,
+    ///  (b) doesn't have a piece under the cursor, and
+    ///  (c) has a piece owned by the current player in one of the neighbouring cells
+    pub fn check_placement(
+        &self,
+        selected_x: usize,
+        selected_y: usize,
+    ) -> Result<(), EventRejection> {
+        if selected_x >= COLS || selected_y >= ROWS {
+            return Err(EventRejection::OutOfBounds);
+        }
+
+        if self.pieces[tile_to_idx(selected_x, selected_y)] != Piece::Empty {
+            return Err(EventRejection::TileOccupied);
+        }
+
+        let neighbour_contains_player_piece = self
+            .get_neighbours(selected_x, selected_y, PieceType::Hound)
+            .iter()
+            .any(|(nx, ny)| self.current_player.owns(self.pieces[tile_to_idx(*nx, *ny)]));
+
+        if !neighbour_contains_player_piece {
+            return Err(EventRejection::NoAdjacentFriend);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the current player summoning a piece of the given type at the
+    /// x/y position would make a match. Doesn't check the placement is valid.
+    pub fn would_match(&self, x: usize, y: usize, piece_type: PieceType) -> bool {
+        let mut board = *self;
+        board.pieces[tile_to_idx(x, y)] = self.current_player.piece(piece_type);
+
+        !board.get_matches().is_empty()
+    }
+
+    /// Counts the number of red cells in the map
+    pub fn count_red_cells(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|piece| matches!(piece, Piece::Player1(_)))
+            .count()
+    }
+
+    /// Gets neighbouring cells for this tile piece at the given x/y tile coordinate
+    pub fn get_neighbours(&self, x: usize, y: usize, piece_type: PieceType) -> Vec<(usize, usize)> {
+        if x == usize::MAX || y == usize::MAX {
+            return vec![];
+        }
+
+        match piece_type {
+            PieceType::Swordsman => vec![(0isize, -1isize), (-1, 0), (1, 0), (0, 1)],
+            PieceType::Hound => vec![
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+            PieceType::Bowman => vec![(0, -1), (0, 1)],
+            _ => {
+                // you're in the wrong place dude
+                return vec![];
+            }
+        }
+        .iter()
+        .filter_map(|(dx, dy)| {
+            let new_x = x.checked_add_signed(*dx).unwrap_or(COLS);
+            let new_y = y.checked_add_signed(*dy).unwrap_or(ROWS);
+
+            if new_x >= COLS || new_y >= ROWS {
+                None
+            } else {
+                Some((new_x, new_y))
+            }
+        })
+        .collect()
+    }
+
+    fn _do_matching(&self, is_horizontal: bool) -> Vec<Match> {
+        let mut result = vec![];
+
+        // check matches
+        let mut expected: Option<PieceType> = match self.pieces[0] {
+            Piece::Empty | Piece::Obstacle(_) => None,
+            Piece::Player0(pt) => Some(pt),
+            Piece::Player1(pt) => Some(pt),
+        };
+        let mut start_idx = 0;
+        let mut length = 0;
+
+        for dim_two in 0..(match is_horizontal {
+            true => ROWS,
+            false => COLS,
+        }) {
+            for dim_one in 0..(match is_horizontal {
+                true => COLS,
+                false => ROWS,
+            }) {
+                let idx = match is_horizontal {
+                    true => tile_to_idx(dim_one, dim_two),
+                    false => tile_to_idx(dim_two, dim_one),
+                };
+
+                // find the current piece type
+                let current_piece_type = match self.pieces[idx] {
+                    Piece::Empty | Piece::Obstacle(_) => None,
+                    Piece::Player0(pt) => Some(pt),
+                    Piece::Player1(pt) => Some(pt),
+                };
+
+                if dim_one == 0 {
+                    if length >= 2 {
+                        result.push(match is_horizontal {
+                            true => Match::Horizontal {
+                                start_idx,
+                                length: length + 1,
+                            },
+                            false => Match::Vertical {
+                                start_idx,
+                                length: length + 1,
+                            },
+                        });
+                    }
+
+                    start_idx = idx;
+                    length = 0;
+                    expected = current_piece_type;
+                    continue;
+                }
+
+                // continue the match
+                let is_matched = match (expected, current_piece_type) {
+                    (Some(pt1), Some(pt2)) => pt1 == pt2,
+                    _ => false,
+                };
+
+                if !is_matched {
+                    // we didn't match, but maybe the previous line was a match
+                    if length >= 2 {
+                        result.push(match is_horizontal {
+                            true => Match::Horizontal {
+                                start_idx,
+                                length: length + 1,
+                            },
+                            false => Match::Vertical {
+                                start_idx,
+                                length: length + 1,
+                            },
+                        });
+                    }
+
+                    // immediately start a new match
+                    start_idx = idx;
+                    length = 0;
+                    expected = current_piece_type;
+                } else {
+                    length += 1;
+                }
+            }
+        }
+
+        // we may be mostly through a match, add it here
+        if length >= 2 {
+            result.push(match is_horizontal {
+                true => Match::Horizontal {
+                    start_idx,
+                    length: length + 1,
+                },
+                false => Match::Vertical {
+                    start_idx,
+                    length: length + 1,
+                },
+            });
+        }
+
+        result
+    }
+
+    /// Gets any three in a row matches.
+    pub fn get_matches(&self) -> Vec<Match> {
+        [self._do_matching(true), self._do_matching(false)].concat()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{
+        state::{GameState, Piece, PieceType},
+        utils::tile_to_idx,
+    };
+
+    #[test]
+    fn test_transposed_positions_are_the_same_board() {
+        let mut state = GameState::default();
+        state.pieces[0] = Piece::Player0(PieceType::Hound);
+        state.num_circles = 2;
+
+        let mut first = state.clone();
+        first.place_piece(1, 0, PieceType::Hound);
+        first.place_piece(0, 1, PieceType::Hound);
+
+        let mut second = state.clone();
+        second.place_piece(0, 1, PieceType::Hound);
+        second.place_piece(1, 0, PieceType::Hound);
+
+        let seen = HashSet::from([state.board, first.board]);
+        assert!(seen.contains(&second.board));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_would_match_leaves_the_board_alone() {
+        let mut state = GameState::default();
+        state.pieces[tile_to_idx(0, 0)] = Piece::Player1(PieceType::Bowman);
+        state.pieces[tile_to_idx(0, 1)] = Piece::Player1(PieceType::Bowman);
+
+        assert!(state.would_match(0, 2, PieceType::Bowman));
+        assert!(!state.would_match(0, 2, PieceType::Hound));
+        assert!(!state.would_match(1, 2, PieceType::Bowman));
+        assert_eq!(state.pieces[tile_to_idx(0, 2)], Piece::Empty);
+    }
+
+    #[test]
+    fn test_placing_on_a_copied_board_matches_the_game() {
+        let mut state = GameState::default();
+        state.pieces[tile_to_idx(0, 0)] = Piece::Player0(PieceType::Hound);
+        state.pieces[tile_to_idx(1, 1)] = Piece::Player1(PieceType::Hound);
+        state.pieces[tile_to_idx(2, 1)] = Piece::Player1(PieceType::Hound);
+        state.num_circles = 1;
+
+        let mut board = state.board;
+        let side_effects = board.place_piece(0, 1, PieceType::Hound, &state.rules);
+
+        assert_eq!(
+            side_effects.len(),
+            state.place_piece(0, 1, PieceType::Hound).len()
+        );
+        assert_eq!(board, state.board);
+        assert_eq!(board.souls_harvested, 2);
+        assert_eq!(board.placements, 1);
+        assert!(board.is_level_over(&state.rules));
+    }
+}
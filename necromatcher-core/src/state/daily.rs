@@ -0,0 +1,214 @@
+//! The daily challenge, a generated level that is the same for everyone on a given date.
+//! The seed comes from the date alone so no network is needed to share a puzzle.
+
+use chrono::{Datelike, Local, NaiveDate};
+
+use super::{level_generator::endless_board_size, solver::shortest_solution, GameMode, GameState};
+
+/// How hard the daily challenge is, using the same scale as endless mode
+pub const DAILY_DIFFICULTY: usize = 3;
+
+/// How many positions the solver can visit when working out par. Almost every
+/// daily challenge is solved well within this, in a fraction of a second.
+const PAR_SOLVER_BUDGET: usize = 100_000;
+
+/// How many moves over par still earns two stars
+const TWO_STAR_MARGIN: usize = 2;
+
+/// The local date, used to pick today's challenge
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// The seed for the given date's challenge. This is a fixed mix of the date
+/// rather than a std hasher so it never changes between builds or platforms.
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    // splitmix64 finaliser
+    let mut z = (date.num_days_from_ce() as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The board size used for every daily challenge
+pub fn daily_board_size() -> (usize, usize) {
+    endless_board_size(DAILY_DIFFICULTY)
+}
+
+/// The fewest moves that clear the level, or None if the solver gave up
+pub fn calculate_par(state: &GameState) -> Option<usize> {
+    shortest_solution(state, PAR_SOLVER_BUDGET).map(|solution| solution.len())
+}
+
+/// Par as shown to the player, `?` when it isn't known
+pub fn par_text(par: Option<usize>) -> String {
+    par.map_or_else(|| "?".to_owned(), |par| par.to_string())
+}
+
+/// Stars out of three for clearing a level in `moves` when par is `par`
+pub fn stars(moves: usize, par: usize) -> usize {
+    if moves <= par {
+        3
+    } else if moves <= par + TWO_STAR_MARGIN {
+        2
+    } else {
+        1
+    }
+}
+
+/// The outcome of a finished daily challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyResult {
+    pub date: NaiveDate,
+    pub seed: u64,
+    pub moves: usize,
+    /// None if the solver couldn't work out par, in which case there are no stars
+    pub par: Option<usize>,
+}
+
+impl DailyResult {
+    pub fn stars(&self) -> Option<usize> {
+        self.par.map(|par| stars(self.moves, par))
+    }
+
+    /// A single line summary that players can paste to each other
+    pub fn share_text(&self) -> String {
+        let stars = self
+            .stars()
+            .map(|stars| format!(" - {stars}/3 stars"))
+            .unwrap_or_default();
+
+        format!(
+            "Necromatcher daily {} - {} moves (par {}){stars} - seed {}",
+            self.date,
+            self.moves,
+            par_text(self.par),
+            self.seed
+        )
+    }
+}
+
+impl GameState {
+    /// The result of the daily challenge, once it has been cleared
+    pub fn daily_result(&self) -> Option<DailyResult> {
+        match self.mode {
+            GameMode::Daily { date, par } if self.is_level_over() => Some(DailyResult {
+                date,
+                seed: daily_seed(date),
+                moves: self.moves_this_level(),
+                par,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::{
+        event::GameEvent,
+        state::{game_event_handler::StateEventHandler, solver::solve, GameMode, GameState},
+    };
+
+    use super::{calculate_par, daily_seed, stars, DailyResult};
+
+    #[test]
+    fn test_daily_seed_depends_only_on_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 4, 14).unwrap();
+        let next = NaiveDate::from_ymd_opt(2024, 4, 15).unwrap();
+
+        assert_eq!(daily_seed(date), daily_seed(date));
+        assert_ne!(daily_seed(date), daily_seed(next));
+    }
+
+    #[test]
+    fn test_daily_challenge_is_the_same_for_everyone() {
+        let date = NaiveDate::from_ymd_opt(2024, 4, 14).unwrap();
+
+        let mut first = GameState::default();
+        first.apply_event(GameEvent::LoadDaily { date }).unwrap();
+        let mut second = GameState::default();
+        second.apply_event(GameEvent::LoadDaily { date }).unwrap();
+
+        assert_eq!(first.pieces, second.pieces);
+        assert_eq!(first.mode, second.mode);
+        assert!(matches!(first.mode, GameMode::Daily { par: Some(par), .. } if par > 0));
+    }
+
+    #[test]
+    fn test_share_text() {
+        let result = DailyResult {
+            date: NaiveDate::from_ymd_opt(2024, 4, 14).unwrap(),
+            seed: 42,
+            moves: 5,
+            par: Some(4),
+        };
+
+        assert_eq!(stars(4, 4), 3);
+        assert_eq!(stars(7, 4), 1);
+        assert_eq!(
+            result.share_text(),
+            "Necromatcher daily 2024-04-14 - 5 moves (par 4) - 2/3 stars - seed 42"
+        );
+        assert_eq!(
+            DailyResult {
+                par: None,
+                ..result
+            }
+            .share_text(),
+            "Necromatcher daily 2024-04-14 - 5 moves (par ?) - seed 42"
+        );
+    }
+
+    #[test]
+    fn test_par_is_the_fewest_moves() {
+        // the first solution a depth first search finds here takes 9 moves
+        let date = NaiveDate::from_ymd_opt(2024, 4, 10).unwrap();
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadDaily { date }).unwrap();
+
+        assert_eq!(state.mode, GameMode::Daily { date, par: Some(3) });
+        assert_eq!(calculate_par(&state), Some(3));
+    }
+
+    #[test]
+    fn test_reset_and_undo_keep_the_par() {
+        let date = NaiveDate::from_ymd_opt(2024, 4, 10).unwrap();
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadDaily { date }).unwrap();
+
+        // a par the search would never give, so working it out again would show
+        state.mode = GameMode::Daily {
+            date,
+            par: Some(99),
+        };
+
+        let (x, y, piece_type) = solve(&state, 100_000).unwrap()[0];
+        state
+            .apply_event(GameEvent::PlacePlayerPiece { x, y, piece_type })
+            .unwrap();
+        state.apply_event(GameEvent::Undo).unwrap();
+        assert_eq!(
+            state.mode,
+            GameMode::Daily {
+                date,
+                par: Some(99)
+            }
+        );
+
+        state.apply_event(GameEvent::Reset).unwrap();
+        assert_eq!(
+            state.mode,
+            GameMode::Daily {
+                date,
+                par: Some(99)
+            }
+        );
+        assert_eq!(
+            state.level_message,
+            "Daily challenge for 2024-04-10. Par is 99 moves."
+        );
+    }
+}
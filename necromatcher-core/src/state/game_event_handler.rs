@@ -0,0 +1,332 @@
+use log::{info, warn};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    event::{EventRejection, GameEvent},
+    state::PieceType,
+    COLS, ROWS,
+};
+
+use super::{
+    level_generator::endless_board_size,
+    level_loader::{StateLevelLoader, NUM_LEVELS},
+    side_effects::SideEffect,
+    GameMode, GameState, LevelFailure,
+};
+
+pub const DEFAULT_DESPAWN_DELAY: f32 = 0.5;
+
+/// How long it takes a piece to fall into place when gravity is on
+pub const FALL_DURATION: f32 = 0.3;
+
+impl GameState {
+    /// Places a player piece and resolves any matches it makes, without validating
+    /// the placement or recording it in the event log
+    pub fn place_piece(&mut self, x: usize, y: usize, piece_type: PieceType) -> Vec<SideEffect> {
+        self.board.place_piece(x, y, piece_type, &self.rules)
+    }
+
+    /// Returns true once every campaign level has been beaten. This only depends on the
+    /// board, so a game rebuilt from the event log agrees on where `Reset` goes.
+    pub fn is_campaign_complete(&self) -> bool {
+        self.mode == GameMode::Campaign
+            && self.current_level + 1 >= NUM_LEVELS
+            && self.is_level_over()
+    }
+
+    /// The index in the event log of the first event belonging to the current level
+    fn level_start_idx(&self) -> usize {
+        self.events
+            .iter()
+            .rposition(|e| {
+                matches!(
+                    e,
+                    GameEvent::LoadLevel { .. }
+                        | GameEvent::GenerateLevel { .. }
+                        | GameEvent::LoadDaily { .. }
+                        | GameEvent::LoadVersus
+                        | GameEvent::NextLevel
+                        | GameEvent::Reset
+                )
+            })
+            .map(|idx| idx + 1)
+            .unwrap_or(0)
+    }
+
+    /// The number of pieces placed so far in the current level
+    pub fn moves_this_level(&self) -> usize {
+        self.placements
+    }
+
+    /// The index in the event log of the last piece placed in the current level
+    fn last_placement_idx(&self) -> Option<usize> {
+        let level_start_idx = self.level_start_idx();
+
+        self.events[level_start_idx..]
+            .iter()
+            .rposition(|e| matches!(e, GameEvent::PlacePlayerPiece { .. }))
+            .map(|idx| idx + level_start_idx)
+    }
+}
+
+pub trait StateEventHandler {
+    fn validate_event(&self, game_event: &mut GameEvent) -> Result<(), EventRejection>;
+    fn apply_event(&mut self, game_event: GameEvent) -> Result<Vec<SideEffect>, EventRejection>;
+}
+
+impl StateEventHandler for GameState {
+    /// Returns Ok() if the event can be applied, possibly mutates the event
+    fn validate_event(&self, game_event: &mut GameEvent) -> Result<(), EventRejection> {
+        match game_event {
+            GameEvent::SeedRng { seed: _seed } => Ok(()),
+            GameEvent::PlacePlayerPiece { x, y, piece_type } => self
+                .check_summon(*x, *y, *piece_type)
+                .map_err(|rejection| match self.level_failure() {
+                    Some(failure) => EventRejection::LevelFailed(failure),
+                    None => rejection,
+                }),
+            GameEvent::LoadLevel { level_id } => {
+                if *level_id >= NUM_LEVELS {
+                    return Err(EventRejection::InvalidLevel);
+                }
+
+                Ok(())
+            }
+            GameEvent::GenerateLevel {
+                width,
+                height,
+                difficulty,
+                ..
+            } => {
+                // the board has to fit on the grid, and difficulty starts at 1
+                if !(3..=COLS).contains(width) || !(3..=ROWS).contains(height) || *difficulty == 0 {
+                    return Err(EventRejection::InvalidLevel);
+                }
+
+                Ok(())
+            }
+            GameEvent::LoadDaily { .. } | GameEvent::LoadVersus => Ok(()),
+            GameEvent::TimeExpired => {
+                if self.rules.time_limit.is_none() {
+                    return Err(EventRejection::NoTimeLimit);
+                }
+
+                if self.is_level_over() || self.time_expired {
+                    return Err(EventRejection::LevelFinished);
+                }
+
+                Ok(())
+            }
+            GameEvent::NextLevel => match self.mode {
+                GameMode::Campaign if (self.current_level + 1) >= NUM_LEVELS => {
+                    Err(EventRejection::LastLevel)
+                }
+                GameMode::Daily { .. } | GameMode::Versus => Err(EventRejection::SingleLevel),
+                _ => Ok(()),
+            },
+            GameEvent::Reset => Ok(()),
+            GameEvent::Undo => {
+                if self.is_campaign_complete() || self.is_level_over() {
+                    return Err(EventRejection::LevelFinished);
+                }
+
+                if self.time_expired {
+                    return Err(EventRejection::LevelFailed(LevelFailure::OutOfTime));
+                }
+
+                if self.last_placement_idx().is_none() {
+                    return Err(EventRejection::NothingToUndo);
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn apply_event(
+        &mut self,
+        mut game_event: GameEvent,
+    ) -> Result<Vec<SideEffect>, EventRejection> {
+        match self.validate_event(&mut game_event) {
+            Ok(_) => match &game_event {
+                GameEvent::SeedRng { seed } => {
+                    info!("Seeded RNG to {seed} in response to GameEvent");
+                    self.rng = ChaCha20Rng::seed_from_u64(*seed);
+                    self.events.push(game_event);
+                    Ok(vec![])
+                }
+                GameEvent::PlacePlayerPiece { x, y, piece_type } => {
+                    info!("Adding player piece");
+
+                    let mut side_effects = self.place_piece(*x, *y, *piece_type);
+
+                    self.events.push(game_event);
+                    side_effects.extend(self.apply_spawn_rule());
+
+                    if self.mode == GameMode::Versus {
+                        self.end_turn();
+                    }
+
+                    if self.is_level_over() {
+                        warn!("Game over man");
+                        side_effects.push(SideEffect::GameOver {
+                            load_another: match self.mode {
+                                GameMode::Campaign => self.current_level + 1 < NUM_LEVELS,
+                                GameMode::Endless { .. } => true,
+                                GameMode::Daily { .. } | GameMode::Versus => false,
+                            },
+                        });
+                    } else if let Some(reason) = self.level_failure() {
+                        side_effects.push(SideEffect::LevelFailed { reason });
+                    }
+
+                    Ok(side_effects)
+                }
+                GameEvent::LoadLevel { level_id } => {
+                    self.load_level(*level_id);
+                    self.current_level = *level_id;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+                GameEvent::GenerateLevel {
+                    seed,
+                    width,
+                    height,
+                    difficulty,
+                } => {
+                    self.load_generated_level(*seed, *width, *height, *difficulty);
+                    self.current_level = 0;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+                GameEvent::LoadDaily { date } => {
+                    self.load_daily(*date);
+                    self.current_level = 0;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+                GameEvent::LoadVersus => {
+                    self.load_versus();
+                    self.current_level = 0;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+                GameEvent::TimeExpired => {
+                    self.time_expired = true;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::LevelFailed {
+                        reason: LevelFailure::OutOfTime,
+                    }])
+                }
+                GameEvent::NextLevel => {
+                    match self.mode {
+                        GameMode::Campaign => self.load_level(self.current_level + 1),
+                        GameMode::Endless { difficulty, .. } => {
+                            // the next seed comes from the current level's rng so runs are repeatable
+                            let seed = self.rng.next_u64();
+                            let (width, height) = endless_board_size(difficulty + 1);
+                            self.load_generated_level(seed, width, height, difficulty + 1);
+                        }
+                        GameMode::Daily { .. } | GameMode::Versus => {
+                            unreachable!("rejected during validation")
+                        }
+                    }
+                    self.current_level += 1;
+                    self.events.push(game_event);
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+                GameEvent::Reset => {
+                    // if we're at the last level, go back to level 1, otherwise just reset
+                    self.events.push(game_event);
+
+                    if self.is_campaign_complete() {
+                        // we're going back to the start
+                        self.load_level(0);
+                        self.current_level = 0;
+
+                        Ok(vec![
+                            SideEffect::FullRespawnTiles,
+                            SideEffect::RemoveGameOverCondition,
+                        ])
+                    } else {
+                        self.reload_level();
+
+                        // a finished daily challenge can be retried, so clear the game over screen
+                        Ok(vec![
+                            SideEffect::FullRespawnTiles,
+                            SideEffect::RemoveGameOverCondition,
+                        ])
+                    }
+                }
+                GameEvent::Undo => {
+                    // validated above so we know there is a placement to remove
+                    let placement_idx = self.last_placement_idx().unwrap();
+                    let level_start_idx = self.level_start_idx();
+
+                    // replay the level from the start, leaving the log as it was before the placement
+                    let replay = self.events[level_start_idx..placement_idx].to_vec();
+                    self.events.truncate(level_start_idx);
+
+                    // undoing doesn't give back any time that has been used
+                    let level_elapsed = self.level_elapsed;
+                    self.reload_level();
+                    self.level_elapsed = level_elapsed;
+
+                    for event in replay {
+                        self.apply_event(event)?;
+                    }
+
+                    Ok(vec![SideEffect::FullRespawnTiles])
+                }
+            },
+            Err(rejection) => {
+                warn!("Unable to apply event {game_event:?} - {rejection}");
+                Err(rejection)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::{EventRejection, GameEvent},
+        state::{Piece, PieceType},
+        utils::tile_to_idx,
+    };
+
+    use super::{GameState, StateEventHandler};
+
+    #[test]
+    fn test_undo_removes_last_placement() {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        let num_squares = state.num_squares;
+
+        state
+            .apply_event(GameEvent::PlacePlayerPiece {
+                x: 1,
+                y: 2,
+                piece_type: PieceType::Swordsman,
+            })
+            .unwrap();
+        assert!(matches!(
+            state.pieces[tile_to_idx(1, 2)],
+            Piece::Player0(PieceType::Swordsman)
+        ));
+
+        state.apply_event(GameEvent::Undo).unwrap();
+        assert!(matches!(state.pieces[tile_to_idx(1, 2)], Piece::Empty));
+        assert_eq!(state.num_squares, num_squares);
+
+        // nothing left to undo
+        assert_eq!(
+            state.apply_event(GameEvent::Undo).unwrap_err(),
+            EventRejection::NothingToUndo
+        );
+        assert!(state.last_placement_idx().is_none());
+    }
+}
@@ -0,0 +1,208 @@
+//! The gravity level rule. After a placement, pieces fall down into empty tiles
+//! (towards row 0), stopping when they land on another piece or a wall. Cleared
+//! pieces can be replaced by red souls from the level's refill queue, and any
+//! matches made along the way are cleared in turn until the board settles.
+
+use crate::{
+    utils::{idx_to_tile, tile_to_idx},
+    COLS, ROWS,
+};
+
+use super::{
+    board::Board,
+    game_event_handler::{DEFAULT_DESPAWN_DELAY, FALL_DURATION},
+    rules::LevelRules,
+    side_effects::SideEffect,
+    Piece, PieceType,
+};
+
+impl Board {
+    /// Lets pieces fall and refill, clearing matches until nothing else changes.
+    /// Each round of falling waits for the previous round of matches to despawn.
+    pub(super) fn settle_board(&mut self, rules: &LevelRules) -> Vec<SideEffect> {
+        let mut side_effects = vec![];
+        let mut delay = 0.;
+        let mut cleared_per_column = [0; COLS];
+
+        loop {
+            let mut falls = self.apply_gravity(delay);
+            falls.extend(self.apply_refill(&rules.refill, &mut cleared_per_column, delay));
+            if !falls.is_empty() {
+                delay += FALL_DURATION;
+            }
+            side_effects.extend(falls);
+
+            let cleared = self.clear_matches();
+            if cleared.is_empty() {
+                break;
+            }
+
+            delay += DEFAULT_DESPAWN_DELAY;
+            for idx in cleared {
+                cleared_per_column[idx_to_tile(idx).0] += 1;
+                side_effects.push(SideEffect::DespawnAtTile { idx, delay });
+            }
+        }
+
+        side_effects
+    }
+
+    /// Moves every piece down as far as it can go within its column
+    fn apply_gravity(&mut self, delay: f32) -> Vec<SideEffect> {
+        let mut side_effects = vec![];
+
+        for x in 0..COLS {
+            // the lowest empty tile a piece could land on
+            let mut landing = None;
+
+            for y in 0..ROWS {
+                let idx = tile_to_idx(x, y);
+
+                match self.pieces[idx] {
+                    Piece::Empty => {
+                        landing = landing.or(Some(y));
+                    }
+                    Piece::Obstacle(_) => landing = None,
+                    piece @ (Piece::Player0(_) | Piece::Player1(_)) => {
+                        let Some(landing_y) = landing else {
+                            continue;
+                        };
+
+                        let to = tile_to_idx(x, landing_y);
+                        self.pieces[to] = piece;
+                        self.pieces[idx] = Piece::Empty;
+                        side_effects.push(SideEffect::MoveTile {
+                            from: idx,
+                            to,
+                            delay,
+                        });
+
+                        // everything between the landing spot and here is now empty
+                        landing = Some(landing_y + 1);
+                    }
+                }
+            }
+        }
+
+        side_effects
+    }
+
+    /// Drops red souls from the refill queue into the top of each column that had
+    /// pieces cleared, one for each cleared piece while there is room
+    fn apply_refill(
+        &mut self,
+        refill: &[PieceType],
+        cleared_per_column: &mut [usize; COLS],
+        delay: f32,
+    ) -> Vec<SideEffect> {
+        let mut side_effects = vec![];
+
+        for (x, cleared) in cleared_per_column.iter_mut().enumerate() {
+            let empty_at_top = (0..ROWS)
+                .rev()
+                .take_while(|y| self.pieces[tile_to_idx(x, *y)] == Piece::Empty)
+                .count();
+
+            for y in (ROWS - empty_at_top..ROWS).take(*cleared) {
+                let Some(piece_type) = refill.get(self.refills_used).copied() else {
+                    break;
+                };
+                self.refills_used += 1;
+
+                let idx = tile_to_idx(x, y);
+                self.pieces[idx] = Piece::Player1(piece_type);
+                side_effects.push(SideEffect::DropInAtTile {
+                    idx,
+                    piece_type,
+                    rows: empty_at_top,
+                    delay,
+                });
+            }
+
+            *cleared = 0;
+        }
+
+        side_effects
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        state::{
+            level_loader::{LevelData, StateLevelLoader},
+            rules::LevelRules,
+            side_effects::SideEffect,
+            GameState, Piece, PieceType,
+        },
+        utils::tile_to_idx,
+    };
+
+    fn gravity_level(pieces: &[((usize, usize), Piece)], refill: &str) -> GameState {
+        let mut level = vec![Piece::Empty; 64];
+        for ((x, y), piece) in pieces {
+            level[tile_to_idx(*x, *y)] = *piece;
+        }
+
+        let mut state = GameState::default();
+        state.load_level_data(LevelData {
+            intro: String::new(),
+            seed: 1,
+            num_triangles: 0,
+            num_squares: 5,
+            num_circles: 5,
+            pieces: level,
+            rules: LevelRules::parse(format!("gravity = true\n{refill}").lines()).unwrap(),
+        });
+        state
+    }
+
+    #[test]
+    fn test_pieces_fall_onto_walls() {
+        let mut state = gravity_level(
+            &[
+                ((0, 0), Piece::Player0(PieceType::Swordsman)),
+                ((2, 2), Piece::Obstacle(PieceType::Wall)),
+                ((2, 6), Piece::Player1(PieceType::Bowman)),
+            ],
+            "",
+        );
+
+        // placed in mid air next to the swordsman, so the hound falls to the floor
+        let side_effects = state.place_piece(1, 1, PieceType::Hound);
+
+        assert_eq!(
+            state.pieces[tile_to_idx(1, 0)],
+            Piece::Player0(PieceType::Hound)
+        );
+        assert_eq!(
+            state.pieces[tile_to_idx(2, 3)],
+            Piece::Player1(PieceType::Bowman)
+        );
+        assert!(matches!(
+            side_effects[1],
+            SideEffect::MoveTile { delay, .. } if delay == 0.
+        ));
+    }
+
+    #[test]
+    fn test_cleared_pieces_are_refilled_and_cascade() {
+        let mut state = gravity_level(
+            &[
+                ((0, 0), Piece::Player1(PieceType::Swordsman)),
+                ((1, 0), Piece::Player1(PieceType::Swordsman)),
+                ((0, 1), Piece::Player1(PieceType::Hound)),
+                ((0, 2), Piece::Player1(PieceType::Hound)),
+            ],
+            "refill = 11",
+        );
+
+        // the swordsmen match, the hounds above fall and a red hound drops in on
+        // top of them, making a second match
+        state.place_piece(2, 0, PieceType::Swordsman);
+
+        assert_eq!(state.num_squares, 4 + 2);
+        assert_eq!(state.num_circles, 5 + 3);
+        assert!(state.pieces.iter().all(|piece| *piece == Piece::Empty));
+    }
+}
@@ -0,0 +1,220 @@
+//! The event log, and rebuilding or restoring a game from it.
+//!
+//! The log records every event that was applied successfully, in order, starting
+//! with the `SeedRng` the state was created with. Replaying it on a fresh state
+//! gives back the same game. There are a few rules that keep that true:
+//!
+//! - loading a level reseeds the rng from the level data without logging it,
+//!   as replaying the load event reseeds it the same way
+//! - `Reset` is always logged, including when it sends a finished campaign back
+//!   to the first level
+//! - `Undo` is never logged, it removes the placement it takes back so the log
+//!   is left exactly as it was before that placement
+//! - rejected events are never logged
+//!
+//! Time passing isn't an event, so the level timer isn't part of the log. A rebuilt
+//! game starts its level's clock from zero, and only a clock that ran out comes back,
+//! through the logged `TimeExpired`.
+
+use crate::event::{EventRejection, GameEvent};
+
+use super::{game_event_handler::StateEventHandler, side_effects::SideEffect, GameState};
+
+/// A full copy of a game that can be restored later
+#[derive(Clone)]
+pub struct GameSnapshot(GameState);
+
+impl GameState {
+    /// The events that led to the current state, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Rebuilds a game by replaying events on a fresh state. Any prefix of another
+    /// game's log gives that game as it was after the last event in the prefix.
+    /// Without a leading `SeedRng` the rng is seeded randomly.
+    pub fn from_events(events: &[GameEvent]) -> Result<Self, EventRejection> {
+        let mut state = Self::default();
+        state.events.clear();
+
+        for event in events {
+            state.apply_event(*event)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Takes a copy of the whole game, including the rng and the event log
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot(self.clone())
+    }
+
+    /// Puts the game back to how it was when the snapshot was taken
+    pub fn restore(&mut self, snapshot: GameSnapshot) -> Vec<SideEffect> {
+        *self = snapshot.0;
+
+        let mut side_effects = vec![
+            SideEffect::FullRespawnTiles,
+            SideEffect::RemoveGameOverCondition,
+        ];
+        if let Some(reason) = self.level_failure() {
+            side_effects.push(SideEffect::LevelFailed { reason });
+        }
+
+        side_effects
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::RngCore;
+
+    use crate::{
+        event::GameEvent,
+        state::{
+            board::Board, game_event_handler::StateEventHandler, level_loader::NUM_LEVELS,
+            parse_level_file, solver::solve, GameMode, LevelFailure, PieceType,
+        },
+    };
+
+    use super::GameState;
+
+    /// Everything about a state that replaying should reproduce
+    fn summary(state: &GameState) -> (Board, GameMode, usize, Vec<GameEvent>, u64) {
+        (
+            state.board,
+            state.mode,
+            state.current_level,
+            state.events().to_vec(),
+            state.rng.clone().next_u64(),
+        )
+    }
+
+    fn place(x: usize, y: usize, piece_type: PieceType) -> GameEvent {
+        GameEvent::PlacePlayerPiece { x, y, piece_type }
+    }
+
+    #[test]
+    fn test_rebuilding_from_any_prefix_matches_the_game_at_that_point() {
+        let mut state = GameState::default();
+        let mut summaries = vec![summary(&state)];
+
+        for event in [
+            GameEvent::LoadLevel { level_id: 0 },
+            place(1, 2, PieceType::Swordsman),
+            GameEvent::Reset,
+            place(1, 2, PieceType::Swordsman),
+            GameEvent::NextLevel,
+            GameEvent::GenerateLevel {
+                seed: 7,
+                width: 5,
+                height: 5,
+                difficulty: 1,
+            },
+            GameEvent::NextLevel,
+            GameEvent::LoadLevel {
+                level_id: NUM_LEVELS - 1,
+            },
+        ] {
+            state.apply_event(event).unwrap();
+            summaries.push(summary(&state));
+        }
+
+        // finish the campaign, then reset back to the first level
+        for (x, y, piece_type) in solve(&state, 400_000).expect("the last level can be solved") {
+            state.apply_event(place(x, y, piece_type)).unwrap();
+            summaries.push(summary(&state));
+        }
+        assert!(state.is_campaign_complete());
+        state.apply_event(GameEvent::Reset).unwrap();
+        summaries.push(summary(&state));
+        assert_eq!(state.get_current_level(), 1);
+
+        // every event is logged once, after the initial seed
+        assert_eq!(state.events().len(), summaries.len());
+
+        for (len, expected) in summaries.iter().enumerate() {
+            let rebuilt = GameState::from_events(&state.events()[..=len]).unwrap();
+            assert_eq!(&summary(&rebuilt), expected, "prefix of {} events", len + 1);
+        }
+    }
+
+    #[test]
+    fn test_rejected_events_are_not_logged() {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        let logged = state.events().len();
+
+        assert!(state.apply_event(place(7, 7, PieceType::Hound)).is_err());
+        assert!(state.apply_event(GameEvent::Undo).is_err());
+        assert_eq!(state.events().len(), logged);
+    }
+
+    #[test]
+    fn test_undo_leaves_the_log_as_it_was_before_the_placement() {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        let before = summary(&state);
+
+        state
+            .apply_event(place(1, 2, PieceType::Swordsman))
+            .unwrap();
+        state.apply_event(GameEvent::Undo).unwrap();
+
+        assert_eq!(summary(&state), before);
+    }
+
+    #[test]
+    fn test_restoring_a_snapshot_round_trips() {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        let snapshot = state.snapshot();
+        let before = summary(&state);
+
+        state
+            .apply_event(place(1, 2, PieceType::Swordsman))
+            .unwrap();
+        state.apply_event(GameEvent::NextLevel).unwrap();
+        assert_ne!(summary(&state), before);
+
+        state.restore(snapshot);
+        assert_eq!(summary(&state), before);
+    }
+
+    #[test]
+    fn test_replaying_restarts_the_level_timer() {
+        let level = format!("{}\ntime_limit = 10", GameState::LEVELS[0].trim_end());
+        let start = GameState::from_level_data(parse_level_file(&level).unwrap());
+        let replay = |state: &GameState| {
+            let mut replayed = start.clone();
+            for event in &state.events()[start.events().len()..] {
+                replayed.apply_event(*event).unwrap();
+            }
+            replayed
+        };
+
+        let mut state = start.clone();
+        state.advance_time(4.);
+        state
+            .apply_event(place(1, 2, PieceType::Swordsman))
+            .unwrap();
+
+        let replayed = replay(&state);
+        assert_eq!(replayed.board, state.board);
+        assert_eq!(state.time_remaining(), Some(6.));
+        assert_eq!(replayed.time_remaining(), Some(10.));
+
+        // running out of time is logged, so that does come back
+        state.advance_time(7.);
+        assert_eq!(
+            replay(&state).level_failure(),
+            Some(LevelFailure::OutOfTime)
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Generates random levels from a seed, for endless mode. Every candidate level
+//! is checked with the [solver](super::solver) so generated levels can always be won.
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use crate::{utils::tile_to_idx, COLS, ROWS};
+
+use super::{
+    level_loader::LevelData, rules::LevelRules, solver::solve, GameState, Piece, PieceType,
+};
+
+/// How many random levels to try before falling back to a known good level
+const MAX_ATTEMPTS: usize = 40;
+
+/// How many positions the solver can visit when checking each attempt
+const SOLVER_BUDGET: usize = 2_000;
+
+/// How many tiles from the player's starting piece a group of red pieces can start
+const GROUP_REACH: usize = 2;
+
+const SUMMONABLE: [PieceType; 3] = [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman];
+
+/// The board size used for endless levels of the given difficulty
+pub fn endless_board_size(difficulty: usize) -> (usize, usize) {
+    let size = (4 + difficulty).min(COLS.min(ROWS));
+    (size, size)
+}
+
+/// Generates a winnable level. The same arguments always produce the same level.
+///
+/// The playable area is `width` x `height` tiles in the middle of the board,
+/// with the rest filled with walls. Higher difficulties add more red pieces
+/// and walls while giving the player fewer souls to start with.
+pub fn generate_level(seed: u64, width: usize, height: usize, difficulty: usize) -> LevelData {
+    let width = width.clamp(3, COLS);
+    let height = height.clamp(3, ROWS);
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate = generate_candidate(&mut rng, seed, width, height, difficulty);
+
+        let mut state = GameState::default();
+        state.load_candidate(&candidate);
+        if solve(&state, SOLVER_BUDGET).is_some() {
+            return candidate;
+        }
+    }
+
+    fallback_level(seed, width, height)
+}
+
+fn generate_candidate(
+    rng: &mut ChaCha20Rng,
+    seed: u64,
+    width: usize,
+    height: usize,
+    difficulty: usize,
+) -> LevelData {
+    let x0 = (COLS - width) / 2;
+    let y0 = (ROWS - height) / 2;
+
+    let mut pieces = vec![Piece::Obstacle(PieceType::Wall); COLS * ROWS];
+    let mut area = vec![];
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            pieces[tile_to_idx(x, y)] = Piece::Empty;
+            area.push((x, y));
+        }
+    }
+
+    // the player's starting piece
+    let (sx, sy) = *area.choose(rng).unwrap();
+    pieces[tile_to_idx(sx, sy)] = Piece::Player0(*SUMMONABLE.choose(rng).unwrap());
+
+    // groups of two red pieces with a gap, so placing a matching piece in the gap harvests
+    // them. They start close to the player's piece, as on bigger boards the player rarely
+    // has the souls to reach groups on the far side.
+    let num_groups = (1 + difficulty / 2).min(5);
+    let near_start = area
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.abs_diff(sx).max(y.abs_diff(sy)) <= GROUP_REACH)
+        .collect::<Vec<_>>();
+    let mut placed_groups = 0;
+    for _ in 0..num_groups * 10 {
+        if placed_groups >= num_groups {
+            break;
+        }
+
+        let piece_type = *SUMMONABLE.choose(rng).unwrap();
+        let (x, y) = *near_start.choose(rng).unwrap();
+        let line = if rng.gen_bool(0.5) {
+            [(x, y), (x + 1, y), (x + 2, y)]
+        } else {
+            [(x, y), (x, y + 1), (x, y + 2)]
+        };
+
+        if line
+            .iter()
+            .any(|(lx, ly)| *lx >= x0 + width || *ly >= y0 + height)
+            || line
+                .iter()
+                .any(|(lx, ly)| pieces[tile_to_idx(*lx, *ly)] != Piece::Empty)
+        {
+            continue;
+        }
+
+        let gap = rng.gen_range(0..3);
+        let previous = pieces.clone();
+        for (i, (lx, ly)) in line.iter().enumerate() {
+            if i != gap {
+                pieces[tile_to_idx(*lx, *ly)] = Piece::Player1(piece_type);
+            }
+        }
+
+        // a group that makes a match on its own would be cleared straight away
+        let mut state = GameState::default();
+        for (idx, piece) in pieces.iter().enumerate() {
+            state.pieces[idx] = *piece;
+        }
+        if state.get_matches().is_empty() {
+            placed_groups += 1;
+        } else {
+            pieces = previous;
+        }
+    }
+
+    // some extra walls to get in the way
+    for _ in 0..difficulty.min(4) {
+        let (x, y) = *area.choose(rng).unwrap();
+        if pieces[tile_to_idx(x, y)] == Piece::Empty {
+            pieces[tile_to_idx(x, y)] = Piece::Obstacle(PieceType::Wall);
+        }
+    }
+
+    // at least one soul for each type of group, or it could never be harvested
+    let max_souls = 3usize.saturating_sub(difficulty / 3).max(1);
+    let has_group = |pt: PieceType| pieces.contains(&Piece::Player1(pt));
+    let mut souls = |pt: PieceType| rng.gen_range(usize::from(has_group(pt))..=max_souls);
+
+    LevelData {
+        intro: format!("The dead are restless. Harvest the souls of the living! (seed {seed})"),
+        seed,
+        num_triangles: souls(PieceType::Bowman),
+        num_squares: souls(PieceType::Swordsman),
+        num_circles: souls(PieceType::Hound),
+        pieces,
+        rules: LevelRules::default(),
+    }
+}
+
+/// A simple level that can always be won, used if no random levels could be solved
+fn fallback_level(seed: u64, width: usize, height: usize) -> LevelData {
+    let x0 = (COLS - width) / 2;
+    let y0 = (ROWS - height) / 2;
+
+    let mut pieces = vec![Piece::Obstacle(PieceType::Wall); COLS * ROWS];
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            pieces[tile_to_idx(x, y)] = Piece::Empty;
+        }
+    }
+
+    // place a swordsman at the gap, next to the hound, to harvest both red swordsmen
+    pieces[tile_to_idx(x0 + 1, y0 + 1)] = Piece::Player0(PieceType::Hound);
+    pieces[tile_to_idx(x0, y0 + 2)] = Piece::Player1(PieceType::Swordsman);
+    pieces[tile_to_idx(x0 + 2, y0 + 2)] = Piece::Player1(PieceType::Swordsman);
+
+    LevelData {
+        intro: format!("The dead are restless. Harvest the souls of the living! (seed {seed})"),
+        seed,
+        num_triangles: 0,
+        num_squares: 2,
+        num_circles: 0,
+        pieces,
+        rules: LevelRules::default(),
+    }
+}
+
+impl GameState {
+    /// Sets up the board from level data without touching the event log
+    fn load_candidate(&mut self, ld: &LevelData) {
+        self.num_triangles = ld.num_triangles;
+        self.num_squares = ld.num_squares;
+        self.num_circles = ld.num_circles;
+
+        for (idx, piece) in ld.pieces.iter().enumerate() {
+            self.pieces[idx] = *piece;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::state::{solver::solve, GameState};
+
+    use super::{endless_board_size, fallback_level, generate_level};
+
+    #[test]
+    fn test_same_seed_generates_same_level() {
+        let first = generate_level(1234, 6, 6, 3);
+        let second = generate_level(1234, 6, 6, 3);
+
+        assert_eq!(first.pieces, second.pieces);
+        assert_eq!(first.num_squares, second.num_squares);
+        assert_eq!(first.num_circles, second.num_circles);
+        assert_eq!(first.num_triangles, second.num_triangles);
+    }
+
+    #[test]
+    fn test_generated_levels_are_winnable() {
+        for seed in 0..10 {
+            let level = generate_level(seed, 6, 6, seed as usize);
+
+            let mut state = GameState::default();
+            state.load_candidate(&level);
+            assert!(
+                solve(&state, 10_000).is_some(),
+                "seed {seed} is not winnable"
+            );
+        }
+    }
+
+    #[test]
+    fn test_harder_levels_rarely_fall_back() {
+        for difficulty in [4, 8] {
+            let (width, height) = endless_board_size(difficulty);
+            let fallbacks = (0..10)
+                .filter(|seed| {
+                    generate_level(*seed, width, height, difficulty).pieces
+                        == fallback_level(*seed, width, height).pieces
+                })
+                .count();
+            assert_eq!(fallbacks, 0, "difficulty {difficulty}");
+        }
+    }
+
+    #[test]
+    fn test_fallback_level_takes_one_move() {
+        let mut state = GameState::default();
+        state.load_candidate(&fallback_level(0, 6, 6));
+        assert_eq!(solve(&state, 100).map(|solution| solution.len()), Some(1));
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+
+use log::{info, warn};
+
+use chrono::NaiveDate;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use super::{
+    daily::{calculate_par, daily_board_size, daily_seed, par_text, DAILY_DIFFICULTY},
+    level_generator::generate_level,
+    rules::LevelRules,
+    GameMode, GameState, Piece, PieceType, Player,
+};
+use crate::{COLS, ROWS};
+
+pub trait StateLevelLoader {
+    fn load_level(&mut self, level_id: usize);
+    fn load_generated_level(&mut self, seed: u64, width: usize, height: usize, difficulty: usize);
+    fn load_daily(&mut self, date: NaiveDate);
+    fn load_versus(&mut self);
+    fn load_level_data(&mut self, level_data: LevelData);
+    fn reload_level(&mut self);
+}
+
+pub const NUM_LEVELS: usize = 5;
+
+impl GameState {
+    pub const LEVELS: [&'static str; NUM_LEVELS] = [
+        include_str!("../../../levels/tutorial.txt"),
+        include_str!("../../../levels/level1.txt"),
+        include_str!("../../../levels/level2.txt"),
+        include_str!("../../../levels/level3.txt"),
+        include_str!("../../../levels/level4.txt"),
+    ];
+
+    /// The board used for two player games
+    pub const VERSUS_LEVEL: &'static str = include_str!("../../../levels/versus.txt");
+}
+
+impl StateLevelLoader for GameState {
+    fn load_level(&mut self, level_id: usize) {
+        if level_id >= Self::LEVELS.len() {
+            warn!("Ignoring level loading request as {level_id} is greater than the length of the available LEVELS {}", Self::LEVELS.len());
+            return;
+        }
+
+        self.mode = GameMode::Campaign;
+        self.load_level_data(parse_level_file(Self::LEVELS[level_id]).expect("built in level"));
+
+        info!("Loaded level {level_id}");
+    }
+
+    fn load_generated_level(&mut self, seed: u64, width: usize, height: usize, difficulty: usize) {
+        self.mode = GameMode::Endless {
+            seed,
+            width,
+            height,
+            difficulty,
+        };
+        self.load_level_data(generate_level(seed, width, height, difficulty));
+
+        info!("Loaded generated level with seed {seed}, size {width}x{height}, difficulty {difficulty}");
+    }
+
+    fn load_daily(&mut self, date: NaiveDate) {
+        self.load_daily_board(date);
+        let par = calculate_par(self);
+        self.start_daily(date, par);
+    }
+
+    fn load_versus(&mut self) {
+        self.load_level_data(parse_level_file(Self::VERSUS_LEVEL).expect("built in level"));
+        self.mode = GameMode::Versus;
+
+        info!("Loaded versus level");
+    }
+
+    fn load_level_data(&mut self, ld: LevelData) {
+        // not logged, replaying the event that loaded the level reseeds it the same way
+        self.rng = ChaCha20Rng::seed_from_u64(ld.seed);
+
+        // update with new level data
+        self.num_triangles = ld.num_triangles;
+        self.num_squares = ld.num_squares;
+        self.num_circles = ld.num_circles;
+
+        self.level_message = ld.intro;
+        self.rules = ld.rules;
+        self.placements = 0;
+        self.refills_used = 0;
+        self.enemy_turns = 0;
+        self.level_elapsed = 0.;
+        self.time_expired = false;
+        self.souls_harvested = 0;
+        self.current_player = Player::Player0;
+        self.waiting_souls = (ld.num_triangles, ld.num_squares, ld.num_circles);
+
+        for (idx, piece) in ld.pieces.into_iter().enumerate() {
+            self.pieces[idx] = piece;
+        }
+    }
+
+    /// Loads the current level again from scratch
+    fn reload_level(&mut self) {
+        match self.mode {
+            GameMode::Campaign => self.load_level(self.current_level),
+            GameMode::Endless {
+                seed,
+                width,
+                height,
+                difficulty,
+            } => self.load_generated_level(seed, width, height, difficulty),
+            // the par only depends on the date, so there's no need to search for it again
+            GameMode::Daily { date, par } => {
+                self.load_daily_board(date);
+                self.start_daily(date, par);
+            }
+            GameMode::Versus => self.load_versus(),
+        }
+    }
+}
+
+impl GameState {
+    fn load_daily_board(&mut self, date: NaiveDate) {
+        let (width, height) = daily_board_size();
+        self.load_level_data(generate_level(
+            daily_seed(date),
+            width,
+            height,
+            DAILY_DIFFICULTY,
+        ));
+    }
+
+    fn start_daily(&mut self, date: NaiveDate, par: Option<usize>) {
+        self.mode = GameMode::Daily { date, par };
+        self.level_message = match par {
+            Some(par) => format!("Daily challenge for {date}. Par is {par} moves."),
+            None => format!("Daily challenge for {date}."),
+        };
+
+        info!(
+            "Loaded daily challenge for {date} with seed {}, par {}",
+            daily_seed(date),
+            par_text(par)
+        );
+    }
+}
+
+/// Why a level file couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelParseError(String);
+
+impl LevelParseError {
+    pub(super) fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for LevelParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse level file - {}", self.0)
+    }
+}
+
+impl std::error::Error for LevelParseError {}
+
+/// Parses a level file: an intro line, the rng seed, the triangle, circle and square
+/// capacities, then a row of piece codes per line and any optional rules.
+/// The built in levels are included in the binary at compile time so can't fail,
+/// but anything else is checked.
+pub fn parse_level_file(data: &str) -> Result<LevelData, LevelParseError> {
+    let mut lines = data.lines();
+
+    let intro = lines
+        .next()
+        .ok_or_else(|| LevelParseError::new("missing intro"))?
+        .to_owned();
+
+    let seed: u64 = lines
+        .next()
+        .ok_or_else(|| LevelParseError::new("missing seed"))?
+        .parse()
+        .map_err(|_| LevelParseError::new("expected the seed to be a u64"))?;
+
+    let numbers = lines
+        .next()
+        .ok_or_else(|| LevelParseError::new("missing capacities"))?
+        .split(',')
+        .map(|item| item.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| LevelParseError::new("expected the capacities to be numbers"))?;
+    if numbers.len() != 3 {
+        return Err(LevelParseError::new(format!(
+            "expected 3 capacities, found {}",
+            numbers.len()
+        )));
+    }
+
+    let mut pieces = Vec::with_capacity(ROWS * COLS);
+    for row in 0..ROWS {
+        let line = lines
+            .next()
+            .ok_or_else(|| LevelParseError::new(format!("missing grid row {row}")))?;
+        let row_pieces = line
+            .split(',')
+            .map(parse_piece)
+            .collect::<Result<Vec<_>, _>>()?;
+        if row_pieces.len() != COLS {
+            return Err(LevelParseError::new(format!(
+                "expected {COLS} pieces in grid row {row}, found {}",
+                row_pieces.len()
+            )));
+        }
+        pieces.extend(row_pieces);
+    }
+
+    // anything after the grid is an optional rule
+    let rules = LevelRules::parse(lines)?;
+
+    Ok(LevelData {
+        seed,
+        intro,
+        num_triangles: numbers[0],
+        num_circles: numbers[1],
+        num_squares: numbers[2],
+        pieces,
+        rules,
+    })
+}
+
+/// Parses a single piece code from a level file
+pub(super) fn parse_piece(code: &str) -> Result<Piece, LevelParseError> {
+    match code.trim() {
+        "0" | "00" => Ok(Piece::Empty),
+        "1" | "01" => Ok(Piece::Player0(PieceType::Hound)),
+        "2" | "02" => Ok(Piece::Player0(PieceType::Swordsman)),
+        "3" | "03" => Ok(Piece::Player0(PieceType::Bowman)),
+        "11" => Ok(Piece::Player1(PieceType::Hound)),
+        "12" => Ok(Piece::Player1(PieceType::Swordsman)),
+        "13" => Ok(Piece::Player1(PieceType::Bowman)),
+        "99" => Ok(Piece::Obstacle(PieceType::Wall)),
+        v => Err(LevelParseError::new(format!(
+            "found piece {v}, expected 0,1,2,3,11,12,13 or 99"
+        ))),
+    }
+}
+
+/// Everything needed to load a level, from a level file or the generator
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelData {
+    pub intro: String,
+    pub seed: u64,
+    pub num_triangles: usize,
+    pub num_squares: usize,
+    pub num_circles: usize,
+    pub pieces: Vec<Piece>,
+    pub rules: LevelRules,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::state::{GameState, Piece, PieceType};
+
+    use super::{parse_level_file, LevelParseError};
+
+    #[test]
+    fn test_built_in_levels_parse() {
+        for level in GameState::LEVELS.iter().chain([&GameState::VERSUS_LEVEL]) {
+            let ld = parse_level_file(level).unwrap();
+            assert_eq!(ld.pieces.len(), 64);
+        }
+    }
+
+    #[test]
+    fn test_bad_level_files_are_errors() {
+        let grid = "0,0,0,0,0,0,0,0\n".repeat(8);
+        let ld = parse_level_file(&format!("intro\n1\n2,3,4\n{grid}")).unwrap();
+        assert_eq!(
+            (ld.num_triangles, ld.num_circles, ld.num_squares),
+            (2, 3, 4)
+        );
+        assert!(ld.pieces.iter().all(|piece| *piece == Piece::Empty));
+
+        assert_eq!(
+            parse_level_file("intro\nseed").unwrap_err(),
+            LevelParseError::new("expected the seed to be a u64")
+        );
+        assert!(parse_level_file(&format!("intro\n1\n2,3\n{grid}")).is_err());
+        assert!(parse_level_file(&format!("intro\n1\n2,3,4\n{}", &grid[2..])).is_err());
+        assert!(parse_level_file(&format!("intro\n1\n2,3,4\n{grid}gravity")).is_err());
+        assert!(parse_level_file("intro\n1\n2,3,4\n0,0,0,0,0,0,0,7").is_err());
+
+        assert_eq!(
+            super::parse_piece("13"),
+            Ok(Piece::Player1(PieceType::Bowman))
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Win conditions for levels. Each kind of objective implements [Objective], and a
+//! level picks one with an `objective = ...` rule line, for example `objective = harvest 6`.
+//! Levels without one have to clear every red soul.
+
+use crate::{utils::tile_to_idx, COLS, ROWS};
+
+use super::{board::Board, level_loader::LevelParseError, Piece, PieceType};
+
+/// Something the player has to do to win a level
+pub trait Objective {
+    /// Returns true once the objective has been met
+    fn is_complete(&self, board: &Board) -> bool;
+
+    /// A short description of what is left to do, shown in the level header
+    fn describe(&self, board: &Board) -> String;
+}
+
+/// Clear every red soul from the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearAll;
+
+impl Objective for ClearAll {
+    fn is_complete(&self, board: &Board) -> bool {
+        board.count_red_cells() == 0
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        format!("Harvest {} more red souls to win", board.count_red_cells())
+    }
+}
+
+/// Clear every red soul of one type, ignoring the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearType(pub PieceType);
+
+impl ClearType {
+    fn remaining(&self, board: &Board) -> usize {
+        board
+            .pieces
+            .iter()
+            .filter(|piece| **piece == Piece::Player1(self.0))
+            .count()
+    }
+}
+
+impl Objective for ClearType {
+    fn is_complete(&self, board: &Board) -> bool {
+        self.remaining(board) == 0
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        format!(
+            "Harvest {} more red {}s to win",
+            self.remaining(board),
+            piece_name(self.0)
+        )
+    }
+}
+
+/// Last for a number of enemy turns, where the red souls take a turn each time the
+/// level's spawn rule is due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Survive {
+    pub turns: usize,
+}
+
+impl Objective for Survive {
+    fn is_complete(&self, board: &Board) -> bool {
+        board.enemy_turns >= self.turns
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        format!(
+            "Survive {} more enemy turns to win",
+            self.turns.saturating_sub(board.enemy_turns)
+        )
+    }
+}
+
+/// Get one of the player's pieces onto a tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReachTile {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Objective for ReachTile {
+    fn is_complete(&self, board: &Board) -> bool {
+        matches!(board.pieces[tile_to_idx(self.x, self.y)], Piece::Player0(_))
+    }
+
+    fn describe(&self, _board: &Board) -> String {
+        "Summon a creature on the marked tile to win".to_owned()
+    }
+}
+
+/// Harvest a number of red souls, whichever they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HarvestSouls {
+    pub count: usize,
+}
+
+impl Objective for HarvestSouls {
+    fn is_complete(&self, board: &Board) -> bool {
+        board.souls_harvested >= self.count
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        format!(
+            "Harvest {} more red souls to win",
+            self.count.saturating_sub(board.souls_harvested)
+        )
+    }
+}
+
+/// Harvest every one of the other player's pieces, used for versus games.
+/// The game also ends in a draw if neither player has anywhere to summon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutlastOpponent;
+
+impl Objective for OutlastOpponent {
+    fn is_complete(&self, board: &Board) -> bool {
+        board.versus_result().is_some()
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        format!(
+            "{}'s turn. Harvest all of your opponent's creatures to win",
+            board.current_player.name()
+        )
+    }
+}
+
+/// The objective a level uses, parsed from its rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelObjective {
+    ClearAll(ClearAll),
+    ClearType(ClearType),
+    Survive(Survive),
+    ReachTile(ReachTile),
+    HarvestSouls(HarvestSouls),
+    OutlastOpponent(OutlastOpponent),
+}
+
+impl Default for LevelObjective {
+    fn default() -> Self {
+        Self::ClearAll(ClearAll)
+    }
+}
+
+impl LevelObjective {
+    pub fn as_objective(&self) -> &dyn Objective {
+        match self {
+            Self::ClearAll(objective) => objective,
+            Self::ClearType(objective) => objective,
+            Self::Survive(objective) => objective,
+            Self::ReachTile(objective) => objective,
+            Self::HarvestSouls(objective) => objective,
+            Self::OutlastOpponent(objective) => objective,
+        }
+    }
+
+    /// Parses an objective rule value like `clear bowman`, `survive 5`,
+    /// `reach 3,4`, `harvest 6` or `versus`
+    pub fn parse(value: &str) -> Result<Self, LevelParseError> {
+        let (kind, args) = value.split_once(' ').unwrap_or((value, ""));
+        let args = args.trim();
+        let invalid = || LevelParseError::new(format!("invalid objective {value}"));
+
+        Ok(match kind {
+            "clear_all" => Self::ClearAll(ClearAll),
+            "clear" => Self::ClearType(ClearType(match args {
+                "swordsman" => PieceType::Swordsman,
+                "hound" => PieceType::Hound,
+                "bowman" => PieceType::Bowman,
+                _ => return Err(invalid()),
+            })),
+            "survive" => Self::Survive(Survive {
+                turns: args.parse().map_err(|_| invalid())?,
+            }),
+            "reach" => {
+                let (x, y) = args.split_once(',').ok_or_else(invalid)?;
+                let x = x.trim().parse().map_err(|_| invalid())?;
+                let y = y.trim().parse().map_err(|_| invalid())?;
+                if x >= COLS || y >= ROWS {
+                    return Err(invalid());
+                }
+                Self::ReachTile(ReachTile { x, y })
+            }
+            "harvest" => Self::HarvestSouls(HarvestSouls {
+                count: args.parse().map_err(|_| invalid())?,
+            }),
+            "versus" => Self::OutlastOpponent(OutlastOpponent),
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+fn piece_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::Swordsman => "swordsman",
+        PieceType::Hound => "hound",
+        PieceType::Bowman => "bowman",
+        PieceType::Wall => "wall",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        state::{GameState, Piece, PieceType},
+        utils::tile_to_idx,
+    };
+
+    use super::{ClearType, LevelObjective, ReachTile};
+
+    #[test]
+    fn test_parse_objectives() {
+        assert_eq!(
+            LevelObjective::parse("clear bowman").unwrap(),
+            LevelObjective::ClearType(ClearType(PieceType::Bowman))
+        );
+        assert_eq!(
+            LevelObjective::parse("reach 3, 4").unwrap(),
+            LevelObjective::ReachTile(ReachTile { x: 3, y: 4 })
+        );
+        assert!(LevelObjective::parse("reach 8,0").is_err());
+        assert!(LevelObjective::parse("clear wall").is_err());
+    }
+
+    #[test]
+    fn test_objectives_decide_when_the_level_is_over() {
+        let mut state = GameState::default();
+        state.pieces[0] = Piece::Player1(PieceType::Swordsman);
+        state.pieces[1] = Piece::Player1(PieceType::Bowman);
+        state.pieces[2] = Piece::Player0(PieceType::Hound);
+
+        state.rules.objective = LevelObjective::default();
+        assert!(!state.is_level_over());
+
+        state.pieces[1] = Piece::Empty;
+        state.rules.objective = LevelObjective::parse("clear bowman").unwrap();
+        assert!(state.is_level_over());
+
+        state.rules.objective = LevelObjective::parse("reach 2,0").unwrap();
+        assert!(state.is_level_over());
+        state.rules.objective = LevelObjective::parse("reach 3,0").unwrap();
+        assert!(!state.is_level_over());
+        assert_eq!(
+            state.pieces[tile_to_idx(2, 0)],
+            Piece::Player0(PieceType::Hound)
+        );
+
+        state.rules.objective = LevelObjective::parse("harvest 2").unwrap();
+        state.souls_harvested = 1;
+        assert!(!state.is_level_over());
+        assert_eq!(
+            state.rules.objective.as_objective().describe(&state),
+            "Harvest 1 more red souls to win"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! A computer opponent that plays as [Player::Player1] in versus games. It picks one of
+//! the same placements a person could make, which the game sends through the event log
+//! as a normal [GameEvent::PlacePlayerPiece](crate::event::GameEvent::PlacePlayerPiece)
+//! so undo and replays work as usual. The simulation also uses it to play single
+//! player levels, where the turn is never handed over.
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{
+    board::Board,
+    rules::LevelRules,
+    solver::{valid_placements, Placement},
+    versus::VersusResult,
+    GameMode, GameState, Player,
+};
+
+/// The score given to a position where one player has won
+const WIN_SCORE: i32 = 1000;
+
+/// How hard the computer opponent tries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpponentDifficulty {
+    /// Picks any valid placement
+    Random,
+    /// Picks the placement that leaves it furthest ahead right now
+    #[default]
+    Greedy,
+    /// Picks the placement that leaves it furthest ahead after the other player's best reply
+    Lookahead,
+}
+
+impl OpponentDifficulty {
+    pub const ALL: [Self; 3] = [Self::Random, Self::Greedy, Self::Lookahead];
+
+    /// The name used in the settings file and shown on screen
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Greedy => "greedy",
+            Self::Lookahead => "lookahead",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    /// The next difficulty, wrapping back around to the easiest
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|d| *d == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// Picks a placement for the player whose turn it is, or None if they can't move.
+/// Placements that score the same are picked between at random.
+pub fn choose_move(
+    state: &GameState,
+    difficulty: OpponentDifficulty,
+    rng: &mut impl Rng,
+) -> Option<Placement> {
+    let moves = valid_placements(state);
+
+    if difficulty == OpponentDifficulty::Random {
+        return moves.choose(rng).copied();
+    }
+
+    let rules = &state.rules;
+    let me = state.current_player;
+    let versus = state.mode == GameMode::Versus;
+    let scored = moves
+        .into_iter()
+        .map(|placement| {
+            let child = play(&state.board, rules, versus, placement);
+            let score = match difficulty {
+                OpponentDifficulty::Lookahead if !child.is_level_over(rules) => {
+                    let replies = valid_placements(&child).into_iter().map(|reply| {
+                        evaluate(&play(&child, rules, versus, reply), rules, versus, me)
+                    });
+                    // the next placement is our own outside versus games, or when the
+                    // other player is stuck and misses their turn
+                    let score = if child.current_player == me {
+                        replies.max().unwrap_or(-WIN_SCORE)
+                    } else {
+                        replies.min().unwrap_or(WIN_SCORE)
+                    };
+                    // a win a move later is worth less than one now, and a loss more
+                    score.clamp(1 - WIN_SCORE, WIN_SCORE - 1)
+                }
+                _ => evaluate(&child, rules, versus, me),
+            };
+            (placement, score)
+        })
+        .collect::<Vec<_>>();
+
+    let best = scored.iter().map(|(_, score)| *score).max()?;
+    let best_moves = scored
+        .into_iter()
+        .filter(|(_, score)| *score == best)
+        .map(|(placement, _)| placement)
+        .collect::<Vec<_>>();
+
+    best_moves.choose(rng).copied()
+}
+
+/// Makes a placement on a copy of the board, handing the turn over in versus games
+fn play(board: &Board, rules: &LevelRules, versus: bool, (x, y, piece_type): Placement) -> Board {
+    let mut child = *board;
+    child.place_piece(x, y, piece_type, rules);
+    if versus {
+        child.end_turn();
+    }
+    child
+}
+
+/// How far ahead the player is, counting their pieces on the board and souls left to
+/// summon against the other player's. Outside versus games that is the red pieces.
+fn evaluate(board: &Board, rules: &LevelRules, versus: bool, player: Player) -> i32 {
+    if board.is_level_over(rules) {
+        return match board.versus_result() {
+            _ if !versus => WIN_SCORE,
+            Some(VersusResult::Won(winner)) if winner == player => WIN_SCORE,
+            Some(VersusResult::Won(_)) => -WIN_SCORE,
+            Some(VersusResult::Draw) | None => 0,
+        };
+    }
+
+    strength(board, player) - strength(board, player.other())
+}
+
+fn strength(board: &Board, player: Player) -> i32 {
+    let (triangles, squares, circles) = if board.current_player == player {
+        (board.num_triangles, board.num_squares, board.num_circles)
+    } else {
+        board.waiting_souls
+    };
+    let on_board = board
+        .pieces
+        .iter()
+        .filter(|piece| player.owns(**piece))
+        .count();
+
+    (triangles + squares + circles + on_board) as i32
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{
+        event::GameEvent,
+        state::{game_event_handler::StateEventHandler, GameState, Piece, PieceType, Player},
+        utils::tile_to_idx,
+    };
+
+    use super::{choose_move, play, OpponentDifficulty};
+
+    /// A versus game on player 2's turn, where player 1 only has two hounds left
+    /// next to player 2's pieces
+    fn computer_to_play(waiting_souls: (usize, usize, usize)) -> GameState {
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadVersus).unwrap();
+
+        for piece in state.pieces.iter_mut() {
+            if matches!(piece, Piece::Player0(_)) {
+                *piece = Piece::Empty;
+            }
+        }
+        state.pieces[tile_to_idx(3, 6)] = Piece::Player0(PieceType::Hound);
+        state.pieces[tile_to_idx(4, 6)] = Piece::Player0(PieceType::Hound);
+
+        state.switch_turns();
+        state.waiting_souls = waiting_souls;
+        state
+    }
+
+    #[test]
+    fn test_opponents_harvest_when_they_can() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+
+        for difficulty in [OpponentDifficulty::Greedy, OpponentDifficulty::Lookahead] {
+            let state = computer_to_play((3, 3, 3));
+            let (x, y, piece_type) = choose_move(&state, difficulty, &mut rng).unwrap();
+
+            assert_eq!(piece_type, PieceType::Hound, "{difficulty:?}");
+            assert_eq!(y, 6, "{difficulty:?}");
+            assert!(x == 2 || x == 5, "{difficulty:?}");
+        }
+    }
+
+    #[test]
+    fn test_lookahead_finds_the_winning_move() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let mut state = computer_to_play((0, 0, 0));
+
+        let (x, y, piece_type) =
+            choose_move(&state, OpponentDifficulty::Lookahead, &mut rng).unwrap();
+        state
+            .apply_event(GameEvent::PlacePlayerPiece { x, y, piece_type })
+            .unwrap();
+
+        assert_eq!(state.versus_winner(), Some(Player::Player1));
+    }
+
+    #[test]
+    fn test_lookahead_plans_its_own_next_move_when_the_other_player_is_stuck() {
+        let mut state = computer_to_play((0, 0, 0));
+        for piece in state.pieces.iter_mut() {
+            if matches!(piece, Piece::Player1(_)) {
+                *piece = Piece::Empty;
+            }
+        }
+        // two summons away from harvesting the last hounds, as player 1 can't reply
+        state.pieces[tile_to_idx(7, 6)] = Piece::Player1(PieceType::Bowman);
+        (state.num_triangles, state.num_squares, state.num_circles) = (0, 0, 3);
+
+        for seed in 0..8 {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let (x, y, _) = choose_move(&state, OpponentDifficulty::Lookahead, &mut rng).unwrap();
+            assert!((x, y) == (6, 6) || (x, y) == (6, 7), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_single_player_placements_keep_the_turn() {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        let souls = state.num_squares;
+
+        let child = play(
+            &state.board,
+            &state.rules,
+            false,
+            (1, 2, PieceType::Swordsman),
+        );
+        assert_eq!(child.current_player, Player::Player0);
+        assert_eq!(child.num_squares, souls - 1);
+    }
+
+    #[test]
+    fn test_difficulty_names_round_trip() {
+        for difficulty in OpponentDifficulty::ALL {
+            assert_eq!(
+                OpponentDifficulty::from_name(difficulty.name()),
+                Some(difficulty)
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Works out what a placement would do before it is made, so the game can show
+//! the player which pieces they are about to clear.
+
+use crate::event::EventRejection;
+
+use super::{side_effects::SideEffect, GameState, PieceType};
+
+/// The predicted outcome of placing a piece
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlacementPreview {
+    /// The tiles that would be cleared by matches, including the placed piece if it
+    /// is part of a match. With gravity on, later cascades are where pieces land.
+    pub cleared: Vec<usize>,
+    /// The triangles, squares and circles the player would harvest
+    pub souls_gained: (usize, usize, usize),
+    /// True if the placement would complete the level's objective
+    pub completes_level: bool,
+}
+
+impl GameState {
+    /// Predicts what placing a piece would do without changing the state,
+    /// or returns why the placement isn't allowed. Doesn't include red souls
+    /// spawned by level rules, as they are random.
+    ///
+    /// This runs every frame for the hovered tile, so it only copies the
+    /// [Board](super::board::Board) and doesn't look through every other placement
+    /// to say why a stuck player is stuck.
+    pub fn preview_placement(
+        &self,
+        x: usize,
+        y: usize,
+        piece_type: PieceType,
+    ) -> Result<PlacementPreview, EventRejection> {
+        self.check_summon(x, y, piece_type)?;
+
+        let mut after = self.board;
+        let side_effects = after.place_piece(x, y, piece_type, &self.rules);
+
+        let cleared = side_effects
+            .iter()
+            .filter_map(|side_effect| match side_effect {
+                SideEffect::SpawnAtTile {
+                    idx,
+                    also_destroy: true,
+                    ..
+                }
+                | SideEffect::DespawnAtTile { idx, .. } => Some(*idx),
+                _ => None,
+            })
+            .collect();
+
+        // the placed piece is paid for before any souls are harvested
+        let cost = |pt| usize::from(piece_type == pt);
+        let souls_gained = (
+            after.num_triangles + cost(PieceType::Bowman) - self.num_triangles,
+            after.num_squares + cost(PieceType::Swordsman) - self.num_squares,
+            after.num_circles + cost(PieceType::Hound) - self.num_circles,
+        );
+
+        Ok(PlacementPreview {
+            cleared,
+            souls_gained,
+            completes_level: after.is_level_over(&self.rules),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::EventRejection,
+        state::{GameState, Piece, PieceType},
+        utils::tile_to_idx,
+    };
+
+    #[test]
+    fn test_preview_predicts_a_match_without_placing() {
+        let mut state = GameState::default();
+        state.pieces[tile_to_idx(0, 0)] = Piece::Player0(PieceType::Hound);
+        state.pieces[tile_to_idx(1, 1)] = Piece::Player1(PieceType::Hound);
+        state.pieces[tile_to_idx(2, 1)] = Piece::Player1(PieceType::Hound);
+        state.pieces[tile_to_idx(5, 5)] = Piece::Player1(PieceType::Bowman);
+        state.num_circles = 1;
+        let before = state.board;
+
+        let preview = state.preview_placement(0, 1, PieceType::Hound).unwrap();
+
+        assert_eq!(state.board, before);
+        assert_eq!(
+            preview.cleared,
+            vec![tile_to_idx(0, 1), tile_to_idx(1, 1), tile_to_idx(2, 1)]
+        );
+        assert_eq!(preview.souls_gained, (0, 0, 2));
+        assert!(!preview.completes_level, "the red bowman is still there");
+    }
+
+    #[test]
+    fn test_preview_rejects_invalid_placements() {
+        let mut state = GameState::default();
+        state.pieces[0] = Piece::Player0(PieceType::Hound);
+        state.num_circles = 1;
+
+        assert_eq!(
+            state.preview_placement(5, 5, PieceType::Hound),
+            Err(EventRejection::NoAdjacentFriend)
+        );
+        assert_eq!(
+            state.preview_placement(1, 0, PieceType::Bowman),
+            Err(EventRejection::NoCapacity)
+        );
+    }
+}
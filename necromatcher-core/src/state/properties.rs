@@ -0,0 +1,203 @@
+//! Property tests that throw random levels and event sequences at the rules engine
+//! and check the invariants the game relies on hold after every event.
+
+use proptest::{collection::vec, option, prelude::*};
+
+use crate::{event::GameEvent, COLS, ROWS};
+
+use super::{
+    board::Board,
+    game_event_handler::StateEventHandler,
+    level_loader::{LevelData, StateLevelLoader},
+    rules::LevelRules,
+    side_effects::SideEffect,
+    GameState, Piece, PieceType,
+};
+
+fn piece_type() -> impl Strategy<Value = PieceType> {
+    prop_oneof![
+        Just(PieceType::Swordsman),
+        Just(PieceType::Hound),
+        Just(PieceType::Bowman),
+    ]
+}
+
+fn piece() -> impl Strategy<Value = Piece> {
+    prop_oneof![
+        6 => Just(Piece::Empty),
+        1 => piece_type().prop_map(Piece::Player0),
+        2 => piece_type().prop_map(Piece::Player1),
+        1 => Just(Piece::Obstacle(PieceType::Wall)),
+    ]
+}
+
+fn rules() -> impl Strategy<Value = LevelRules> {
+    (
+        option::of(1..4usize),
+        any::<bool>(),
+        vec(piece_type(), 0..4),
+        option::of(1..10usize),
+        option::of(Just(30.)),
+    )
+        .prop_map(
+            |(spawn_every, gravity, refill, max_moves, time_limit)| LevelRules {
+                spawn_every,
+                gravity,
+                refill,
+                max_moves,
+                time_limit,
+                ..LevelRules::default()
+            },
+        )
+}
+
+fn level() -> impl Strategy<Value = LevelData> {
+    (
+        any::<u64>(),
+        (0..4usize, 0..4usize, 0..4usize),
+        vec(piece(), ROWS * COLS),
+        rules(),
+    )
+        .prop_map(
+            |(seed, (num_triangles, num_squares, num_circles), pieces, rules)| LevelData {
+                intro: String::new(),
+                seed,
+                num_triangles,
+                num_squares,
+                num_circles,
+                pieces,
+                rules,
+            },
+        )
+}
+
+/// An event that loads a level, and is logged so the level can be reloaded
+fn load_event() -> impl Strategy<Value = GameEvent> {
+    prop_oneof![
+        (0..6usize).prop_map(|level_id| GameEvent::LoadLevel { level_id }),
+        Just(GameEvent::LoadVersus),
+        (any::<u64>(), 2..7usize, 2..7usize, 0..3usize).prop_map(
+            |(seed, width, height, difficulty)| GameEvent::GenerateLevel {
+                seed,
+                width,
+                height,
+                difficulty,
+            }
+        ),
+    ]
+}
+
+/// A placement, sometimes off the board
+fn placement() -> impl Strategy<Value = GameEvent> {
+    (0..COLS + 1, 0..ROWS + 1, piece_type())
+        .prop_map(|(x, y, piece_type)| GameEvent::PlacePlayerPiece { x, y, piece_type })
+}
+
+/// The events that happen while playing a level
+fn level_event() -> impl Strategy<Value = GameEvent> {
+    prop_oneof![
+        20 => placement(),
+        1 => Just(GameEvent::TimeExpired),
+    ]
+}
+
+/// Mostly placements, with the occasional other event
+fn event() -> impl Strategy<Value = GameEvent> {
+    prop_oneof![
+        20 => placement(),
+        3 => Just(GameEvent::Undo),
+        1 => Just(GameEvent::Reset),
+        1 => Just(GameEvent::NextLevel),
+        1 => Just(GameEvent::TimeExpired),
+        1 => load_event(),
+    ]
+}
+
+/// Plays the side effects of an event onto a copy of the board the way the game
+/// draws them, or returns None if they ask for a full redraw
+fn replay_side_effects(before: &Board, side_effects: &[SideEffect]) -> Option<[Piece; 64]> {
+    let mut pieces = before.pieces;
+
+    for side_effect in side_effects {
+        match *side_effect {
+            SideEffect::SpawnAtTile {
+                idx,
+                piece_type,
+                is_player_owned,
+                also_destroy,
+            } => {
+                pieces[idx] = match (also_destroy, is_player_owned) {
+                    (true, _) => Piece::Empty,
+                    (false, true) => Piece::Player0(piece_type),
+                    (false, false) => Piece::Player1(piece_type),
+                }
+            }
+            SideEffect::DespawnAtTile { idx, .. } => pieces[idx] = Piece::Empty,
+            SideEffect::MoveTile { from, to, .. } => {
+                pieces[to] = pieces[from];
+                pieces[from] = Piece::Empty;
+            }
+            SideEffect::DropInAtTile {
+                idx, piece_type, ..
+            } => pieces[idx] = Piece::Player1(piece_type),
+            SideEffect::FullRespawnTiles => return None,
+            _ => {}
+        }
+    }
+
+    Some(pieces)
+}
+
+/// Applies an event and checks the state is still consistent
+fn apply_and_check(state: &mut GameState, event: GameEvent) -> Result<(), TestCaseError> {
+    let before = state.clone();
+
+    // soul counts are unsigned, so a placement without capacity would panic here
+    match state.apply_event(event) {
+        Ok(side_effects) => {
+            // cleared tiles are despawned, and everything else the game draws matches the state
+            if let Some(pieces) = replay_side_effects(&before.board, &side_effects) {
+                prop_assert_eq!(pieces, state.pieces, "after {:?}", event);
+            }
+        }
+        Err(rejection) => {
+            prop_assert_eq!(state.board, before.board, "rejected with {}", rejection);
+            prop_assert_eq!(state.events(), before.events());
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Random boards and rules, which can't be reloaded as they aren't in the log,
+    /// so only the events played within a level are used
+    #[test]
+    fn random_levels_stay_consistent(level in level(), events in vec(level_event(), 1..40)) {
+        let mut state = GameState::default();
+        state.load_level_data(level);
+
+        for event in events {
+            apply_and_check(&mut state, event)?;
+        }
+    }
+
+    #[test]
+    fn replaying_the_log_gives_the_same_game(
+        load in load_event(),
+        events in vec(event(), 0..40),
+    ) {
+        let mut state = GameState::default();
+        for event in std::iter::once(load).chain(events) {
+            apply_and_check(&mut state, event)?;
+        }
+
+        let replayed = GameState::from_events(state.events()).unwrap();
+        prop_assert_eq!(replayed.board, state.board);
+        prop_assert_eq!(replayed.mode, state.mode);
+        prop_assert_eq!(replayed.current_level, state.current_level);
+        prop_assert_eq!(replayed.events(), state.events());
+    }
+}
@@ -0,0 +1,61 @@
+//! The randomness API for level rules.
+//!
+//! Every draw comes from the [GameState]'s seeded rng, which is reseeded by the
+//! [GameEvent::SeedRng](crate::event::GameEvent::SeedRng) logged whenever a level
+//! is loaded. As long as rules only draw while an event is being applied, replaying the
+//! event log (for example when undoing) reproduces every draw exactly.
+
+use rand::seq::SliceRandom;
+
+use super::{GameState, Piece, PieceType};
+
+impl GameState {
+    /// Picks a random item from the slice, or None if it is empty
+    pub fn random_choice<T: Copy>(&mut self, items: &[T]) -> Option<T> {
+        items.choose(&mut self.rng).copied()
+    }
+
+    /// Picks the index of a random empty tile, or None if the board is full
+    pub fn random_empty_tile(&mut self) -> Option<usize> {
+        let empty = self
+            .pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, piece)| **piece == Piece::Empty)
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+
+        self.random_choice(&empty)
+    }
+
+    /// Picks a piece type with a probability proportional to its weight.
+    /// Returns None if there are no piece types with a weight above zero.
+    pub fn random_piece_type(&mut self, weights: &[(PieceType, u32)]) -> Option<PieceType> {
+        weights
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .ok()
+            .map(|(piece_type, _)| *piece_type)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::state::{GameState, Piece, PieceType};
+
+    #[test]
+    fn test_random_draws_skip_impossible_options() {
+        let mut state = GameState::default();
+        state.board.pieces = [Piece::Obstacle(PieceType::Wall); 64];
+        state.pieces[10] = Piece::Empty;
+
+        for _ in 0..20 {
+            assert_eq!(state.random_empty_tile(), Some(10));
+            assert_eq!(
+                state.random_piece_type(&[(PieceType::Hound, 0), (PieceType::Bowman, 3)]),
+                Some(PieceType::Bowman)
+            );
+        }
+
+        assert_eq!(state.random_piece_type(&[(PieceType::Hound, 0)]), None);
+    }
+}
@@ -0,0 +1,391 @@
+//! Optional rules that change how a level plays. Level files turn them on with
+//! `key = value` lines after the grid, for example `spawn_every = 3`.
+
+use crate::event::{EventRejection, GameEvent};
+
+use super::{
+    board::Board,
+    game_event_handler::StateEventHandler,
+    level_loader::{parse_piece, LevelParseError},
+    objectives::LevelObjective,
+    side_effects::SideEffect,
+    solver::valid_placements,
+    GameState, LevelFailure, Piece, PieceType,
+};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LevelRules {
+    /// A new red soul appears on a random empty tile after every this many placements
+    pub spawn_every: Option<usize>,
+    /// Pieces fall down into empty tiles below them, stopping at walls
+    pub gravity: bool,
+    /// Red souls that drop in from the top of a column to replace cleared pieces,
+    /// in order, while gravity is on. Written as piece codes, e.g. `refill = 12,11,13`
+    pub refill: Vec<PieceType>,
+    /// The level is failed if it isn't cleared within this many placements
+    pub max_moves: Option<usize>,
+    /// The level is failed if it isn't cleared within this many seconds
+    pub time_limit: Option<f32>,
+    /// What the player has to do to win the level
+    pub objective: LevelObjective,
+}
+
+impl LevelRules {
+    /// Parses the rule lines at the end of a level file
+    pub fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Self, LevelParseError> {
+        let mut rules = Self::default();
+
+        for line in lines.map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                LevelParseError::new(format!("expected rule {line} to be key = value"))
+            })?;
+            let value = value.trim();
+            let invalid = || LevelParseError::new(format!("invalid value {value} for rule {line}"));
+
+            match key.trim() {
+                "spawn_every" => {
+                    let every = value.parse().map_err(|_| invalid())?;
+                    if every == 0 {
+                        return Err(LevelParseError::new("spawn_every must be at least 1"));
+                    }
+                    rules.spawn_every = Some(every);
+                }
+                "gravity" => rules.gravity = value == "true",
+                "objective" => rules.objective = LevelObjective::parse(value)?,
+                "max_moves" => rules.max_moves = Some(value.parse().map_err(|_| invalid())?),
+                "time_limit" => {
+                    let limit: f32 = value.parse().map_err(|_| invalid())?;
+                    if !limit.is_finite() || limit <= 0. {
+                        return Err(invalid());
+                    }
+                    rules.time_limit = Some(limit);
+                }
+                "refill" => {
+                    rules.refill = value
+                        .split(',')
+                        .map(|code| match parse_piece(code)? {
+                            Piece::Player1(pt) => Ok(pt),
+                            other => Err(LevelParseError::new(format!(
+                                "refill pieces must be red souls, found {other:?}"
+                            ))),
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                other => return Err(LevelParseError::new(format!("unknown rule {other}"))),
+            }
+        }
+
+        if matches!(rules.objective, LevelObjective::Survive(_)) && rules.spawn_every.is_none() {
+            return Err(LevelParseError::new(
+                "survive needs a spawn_every rule, as the red souls only take a turn when they spawn",
+            ));
+        }
+
+        Ok(rules)
+    }
+}
+
+impl Board {
+    /// Why no more pieces can be placed, if the player is stuck
+    pub fn stuck_reason(&self) -> Option<LevelFailure> {
+        let summonable = [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman];
+
+        if !summonable.iter().any(|pt| self.has_capacity(*pt)) {
+            Some(LevelFailure::OutOfSouls)
+        } else if valid_placements(self).is_empty() {
+            Some(LevelFailure::NoValidPlacements)
+        } else {
+            None
+        }
+    }
+}
+
+impl GameState {
+    /// How many more pieces can be placed, if the level has a move budget
+    pub fn moves_remaining(&self) -> Option<usize> {
+        self.rules
+            .max_moves
+            .map(|max| max.saturating_sub(self.moves_this_level()))
+    }
+
+    /// How many seconds are left, if the level has a time limit
+    pub fn time_remaining(&self) -> Option<f32> {
+        self.rules
+            .time_limit
+            .map(|limit| (limit - self.level_elapsed).max(0.))
+    }
+
+    /// Why the current level has been lost, if it has
+    pub fn level_failure(&self) -> Option<LevelFailure> {
+        if self.is_level_over() {
+            None
+        } else {
+            self.limit_failure().or_else(|| self.stuck_reason())
+        }
+    }
+
+    /// Whether the level's time limit or move budget has run out
+    fn limit_failure(&self) -> Option<LevelFailure> {
+        if self.time_expired {
+            Some(LevelFailure::OutOfTime)
+        } else if self.moves_remaining() == Some(0) {
+            Some(LevelFailure::OutOfMoves)
+        } else {
+            None
+        }
+    }
+
+    /// Checks a placement against the level's limits and the board, without looking
+    /// at any other placements. One that passes also means the player isn't stuck.
+    pub(super) fn check_summon(
+        &self,
+        x: usize,
+        y: usize,
+        piece_type: PieceType,
+    ) -> Result<(), EventRejection> {
+        if let Some(failure) = self.limit_failure().filter(|_| !self.is_level_over()) {
+            return Err(EventRejection::LevelFailed(failure));
+        }
+
+        if !self.has_capacity(piece_type) {
+            return Err(EventRejection::NoCapacity);
+        }
+
+        self.check_placement(x, y)
+    }
+
+    /// Counts down the level's time limit, sending [GameEvent::TimeExpired]
+    /// through the event log when it runs out
+    pub fn advance_time(&mut self, delta_seconds: f32) -> Vec<SideEffect> {
+        if self.rules.time_limit.is_none() || self.is_level_over() || self.level_failure().is_some()
+        {
+            return vec![];
+        }
+
+        self.level_elapsed += delta_seconds;
+
+        if self.time_remaining() == Some(0.) {
+            self.apply_event(GameEvent::TimeExpired).unwrap_or_default()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Spawns a red soul on a random empty tile if the spawn rule is due after the
+    /// latest placement. The piece type is weighted towards the red souls already
+    /// on the board, and is never one that would immediately make a match.
+    pub(super) fn apply_spawn_rule(&mut self) -> Vec<SideEffect> {
+        let Some(every) = self.rules.spawn_every else {
+            return vec![];
+        };
+
+        if self.is_level_over() || !self.moves_this_level().is_multiple_of(every) {
+            return vec![];
+        }
+
+        // the turn counts even if there's nowhere to spawn
+        self.enemy_turns += 1;
+
+        let Some(idx) = self.random_empty_tile() else {
+            return vec![];
+        };
+
+        let weights = [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman].map(|pt| {
+            let on_board = self
+                .pieces
+                .iter()
+                .filter(|piece| **piece == Piece::Player1(pt))
+                .count();
+
+            self.pieces[idx] = Piece::Player1(pt);
+            let makes_match = !self.get_matches().is_empty();
+            self.pieces[idx] = Piece::Empty;
+
+            (pt, if makes_match { 0 } else { 1 + on_board as u32 })
+        });
+        let Some(piece_type) = self.random_piece_type(&weights) else {
+            return vec![];
+        };
+
+        self.pieces[idx] = Piece::Player1(piece_type);
+
+        vec![SideEffect::SpawnAtTile {
+            idx,
+            piece_type,
+            is_player_owned: false,
+            also_destroy: false,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::{EventRejection, GameEvent},
+        state::{
+            game_event_handler::StateEventHandler,
+            level_loader::{LevelData, StateLevelLoader},
+            side_effects::SideEffect,
+            GameState, LevelFailure, Piece, PieceType,
+        },
+    };
+
+    use super::LevelRules;
+
+    fn level_with_rules(rules: &str) -> GameState {
+        let mut pieces = vec![Piece::Empty; 64];
+        pieces[0] = Piece::Player0(PieceType::Hound);
+        pieces[63] = Piece::Player1(PieceType::Bowman);
+
+        let mut state = GameState::default();
+        state.load_level_data(LevelData {
+            intro: String::new(),
+            seed: 55,
+            num_triangles: 0,
+            num_squares: 0,
+            num_circles: 5,
+            pieces,
+            rules: LevelRules::parse(rules.lines()).unwrap(),
+        });
+        state
+    }
+
+    fn try_place(
+        state: &mut GameState,
+        x: usize,
+        y: usize,
+    ) -> Result<Vec<SideEffect>, EventRejection> {
+        state.apply_event(GameEvent::PlacePlayerPiece {
+            x,
+            y,
+            piece_type: PieceType::Hound,
+        })
+    }
+
+    fn place(state: &mut GameState, x: usize, y: usize) {
+        try_place(state, x, y).unwrap();
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(LevelRules::parse("\n".lines()), Ok(LevelRules::default()));
+        assert_eq!(
+            LevelRules::parse("spawn_every = 3\n".lines())
+                .unwrap()
+                .spawn_every,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_replaying_the_log_reproduces_spawns() {
+        let mut state = level_with_rules("spawn_every = 2");
+        place(&mut state, 1, 1);
+        place(&mut state, 2, 2);
+        assert_eq!(
+            state.count_red_cells(),
+            2,
+            "a red soul spawns every 2 moves"
+        );
+
+        // the same seed and placements must put the spawn in the same place
+        let mut replayed = level_with_rules("spawn_every = 2");
+        for event in state
+            .events
+            .iter()
+            .skip_while(|e| matches!(e, GameEvent::SeedRng { .. }))
+        {
+            replayed.apply_event(*event).unwrap();
+        }
+        assert_eq!(replayed.pieces, state.pieces);
+    }
+
+    #[test]
+    fn test_surviving_counts_enemy_turns() {
+        let mut state = level_with_rules("spawn_every = 2\nobjective = survive 2");
+        place(&mut state, 1, 1);
+        place(&mut state, 2, 2);
+        place(&mut state, 3, 3);
+        assert_eq!(state.enemy_turns, 1);
+        assert!(
+            !state.is_level_over(),
+            "the red souls have only had one turn"
+        );
+        assert_eq!(
+            state.rules.objective.as_objective().describe(&state),
+            "Survive 1 more enemy turns to win"
+        );
+
+        place(&mut state, 4, 4);
+        assert!(state.is_level_over());
+
+        // the red souls only take turns when they spawn
+        assert!(LevelRules::parse("objective = survive 2".lines()).is_err());
+    }
+
+    #[test]
+    fn test_move_budget_fails_the_level() {
+        let mut state = level_with_rules("max_moves = 2");
+        place(&mut state, 1, 1);
+        assert_eq!(state.moves_remaining(), Some(1));
+
+        let side_effects = state
+            .apply_event(GameEvent::PlacePlayerPiece {
+                x: 2,
+                y: 2,
+                piece_type: PieceType::Hound,
+            })
+            .unwrap();
+        assert!(matches!(
+            side_effects.last(),
+            Some(SideEffect::LevelFailed {
+                reason: LevelFailure::OutOfMoves
+            })
+        ));
+
+        // no more placements are allowed until a move is taken back
+        assert_eq!(
+            try_place(&mut state, 3, 3).unwrap_err(),
+            EventRejection::LevelFailed(LevelFailure::OutOfMoves)
+        );
+        assert_eq!(state.moves_this_level(), 2);
+    }
+
+    #[test]
+    fn test_time_limit_fails_the_level() {
+        let mut state = level_with_rules("time_limit = 10");
+
+        assert!(state.advance_time(9.).is_empty());
+        assert!(matches!(
+            state.advance_time(2.)[..],
+            [SideEffect::LevelFailed {
+                reason: LevelFailure::OutOfTime
+            }]
+        ));
+        assert_eq!(state.level_failure(), Some(LevelFailure::OutOfTime));
+
+        assert!(try_place(&mut state, 1, 1).is_err());
+        assert_eq!(state.moves_this_level(), 0);
+    }
+
+    #[test]
+    fn test_running_out_of_souls_fails_the_level() {
+        let mut state = level_with_rules("");
+        state.num_circles = 1;
+
+        let side_effects = state
+            .apply_event(GameEvent::PlacePlayerPiece {
+                x: 1,
+                y: 1,
+                piece_type: PieceType::Hound,
+            })
+            .unwrap();
+
+        assert!(matches!(
+            side_effects.last(),
+            Some(SideEffect::LevelFailed {
+                reason: LevelFailure::OutOfSouls
+            })
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! Side effects describe how the game state changed after an event, so the
+//! game knows what to spawn, animate and play without diffing the whole state.
+
+use crate::event::EventRejection;
+
+use super::{LevelFailure, PieceType};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::event::Event))]
+pub enum SideEffect {
+    /// Spawn a visual entity at the given tile. Includes the piece
+    /// type because it may immediately be despawned in the game state
+    SpawnAtTile {
+        idx: usize,
+        piece_type: PieceType,
+        is_player_owned: bool,
+        also_destroy: bool,
+    },
+    /// Despawn the visual entity at the given tile after a delay
+    DespawnAtTile { idx: usize, delay: f32 },
+    /// Move the visual entity at one tile to another after a delay, e.g. when it falls
+    MoveTile { from: usize, to: usize, delay: f32 },
+    /// Spawn a red piece above the board that falls the given number of rows into the tile
+    DropInAtTile {
+        idx: usize,
+        piece_type: PieceType,
+        rows: usize,
+        delay: f32,
+    },
+    /// Destroy all visual tiles and respawn them
+    FullRespawnTiles,
+    /// The game is over
+    GameOver { load_another: bool },
+    /// Undo the game over state
+    RemoveGameOverCondition,
+    /// The player lost the current level, and has to undo or reset to continue
+    LevelFailed { reason: LevelFailure },
+    /// An event couldn't be applied, e.g. a piece was placed somewhere it isn't allowed
+    Rejected { reason: EventRejection },
+}
@@ -0,0 +1,335 @@
+//! Plays a level many times with a simple strategy and reports how it went, so
+//! designers can balance a level's starting souls with numbers rather than guesses.
+//!
+//! Each run reseeds the rng, so levels with spawn or refill rules play out
+//! differently each time. Time limits are ignored, as no time passes between moves.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use crate::event::GameEvent;
+
+use super::{
+    game_event_handler::StateEventHandler,
+    level_loader::{LevelData, StateLevelLoader},
+    opponent::{choose_move, OpponentDifficulty},
+    solver::{solve, valid_placements, Placement},
+    versus::VersusResult,
+    GameState, LevelFailure, PieceType, Player,
+};
+
+/// How many positions the solver strategy can visit each time it plans
+const SOLVER_BUDGET: usize = 20_000;
+
+/// Runs that go on longer than this are given up on, in case a level keeps refilling
+const MAX_PLACEMENTS: usize = 500;
+
+/// How the simulated player picks their placements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Picks any valid placement
+    Random,
+    /// Picks the placement that leaves the most of their pieces and souls against the red pieces
+    Greedy,
+    /// Follows the solver's plan, playing greedily if the solver gives up
+    Solver,
+}
+
+impl Strategy {
+    pub const ALL: [Self; 3] = [Self::Random, Self::Greedy, Self::Solver];
+
+    /// The name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Greedy => "greedy",
+            Self::Solver => "solver",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// How a single run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    /// The second player won a versus game
+    Lost,
+    /// Neither player could summon in a versus game
+    Draw,
+    Failed(LevelFailure),
+    /// Still going after hundreds of placements, or the strategy had no move to make
+    GaveUp,
+}
+
+/// What happened in a single run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    pub outcome: Outcome,
+    /// Every placement made, in order
+    pub placements: Vec<Placement>,
+    /// The triangles, squares and circles left at the end
+    pub souls_left: (usize, usize, usize),
+}
+
+impl RunResult {
+    /// How many pieces of the given type were summoned
+    pub fn summoned(&self, piece_type: PieceType) -> usize {
+        self.placements
+            .iter()
+            .filter(|(_, _, pt)| *pt == piece_type)
+            .count()
+    }
+
+    /// How many souls of the given type were left at the end
+    pub fn left(&self, piece_type: PieceType) -> usize {
+        let (triangles, squares, circles) = self.souls_left;
+        match piece_type {
+            PieceType::Swordsman => squares,
+            PieceType::Hound => circles,
+            PieceType::Bowman => triangles,
+            PieceType::Wall => 0,
+        }
+    }
+}
+
+/// The results of playing a level many times with one strategy
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub strategy: Strategy,
+    pub runs: Vec<RunResult>,
+}
+
+impl SimulationReport {
+    /// The fraction of runs that were won, from 0 to 1
+    pub fn win_rate(&self) -> f32 {
+        self.fraction(|run| run.outcome == Outcome::Won)
+    }
+
+    /// The fraction of runs that ended in the given way, from 0 to 1
+    pub fn outcome_rate(&self, outcome: Outcome) -> f32 {
+        self.fraction(|run| run.outcome == outcome)
+    }
+
+    /// The mean number of placements made per run
+    pub fn average_placements(&self) -> f32 {
+        self.average(|run| run.placements.len())
+    }
+
+    /// The mean number of pieces of the given type summoned per run
+    pub fn average_summoned(&self, piece_type: PieceType) -> f32 {
+        self.average(|run| run.summoned(piece_type))
+    }
+
+    /// The mean number of souls of the given type left at the end of a run
+    pub fn average_left(&self, piece_type: PieceType) -> f32 {
+        self.average(|run| run.left(piece_type))
+    }
+
+    fn fraction(&self, filter: impl Fn(&RunResult) -> bool) -> f32 {
+        self.average(|run| usize::from(filter(run)))
+    }
+
+    fn average(&self, value: impl Fn(&RunResult) -> usize) -> f32 {
+        if self.runs.is_empty() {
+            return 0.;
+        }
+        self.runs.iter().map(value).sum::<usize>() as f32 / self.runs.len() as f32
+    }
+}
+
+impl GameState {
+    /// A fresh state with the given level loaded, for simulating edited level files.
+    /// The level isn't in the event log, so it can't be reset or undone back to.
+    pub fn from_level_data(level_data: LevelData) -> Self {
+        let mut state = Self::default();
+        state.load_level_data(level_data);
+        state
+    }
+}
+
+/// Plays the level in `start` from where it is `runs` times. The same seed always gives
+/// the same report. In versus games the strategy plays both sides, and wins for the
+/// second player count as [Outcome::Lost].
+pub fn simulate(start: &GameState, strategy: Strategy, runs: usize, seed: u64) -> SimulationReport {
+    SimulationReport {
+        strategy,
+        runs: (0..runs as u64)
+            .map(|run| play_once(start, strategy, seed.wrapping_add(run)))
+            .collect(),
+    }
+}
+
+/// Plays the level in `start` once, until it is won, lost or given up on
+pub fn play_once(start: &GameState, strategy: Strategy, seed: u64) -> RunResult {
+    let mut state = start.clone();
+    state
+        .apply_event(GameEvent::SeedRng { seed })
+        .expect("seeding is always allowed");
+
+    let mut player = SimulatedPlayer {
+        strategy,
+        rng: ChaCha20Rng::seed_from_u64(seed),
+        plan: vec![],
+        solver_gave_up: false,
+    };
+    let mut placements = vec![];
+
+    let outcome = loop {
+        if state.is_level_over() {
+            break match state.versus_result() {
+                Some(VersusResult::Won(Player::Player1)) => Outcome::Lost,
+                Some(VersusResult::Draw) => Outcome::Draw,
+                Some(VersusResult::Won(Player::Player0)) | None => Outcome::Won,
+            };
+        }
+        if let Some(reason) = state.level_failure() {
+            break Outcome::Failed(reason);
+        }
+        if placements.len() >= MAX_PLACEMENTS {
+            break Outcome::GaveUp;
+        }
+
+        let Some((x, y, piece_type)) = player.next_move(&state) else {
+            break Outcome::GaveUp;
+        };
+        if state
+            .apply_event(GameEvent::PlacePlayerPiece { x, y, piece_type })
+            .is_err()
+        {
+            break Outcome::GaveUp;
+        }
+        placements.push((x, y, piece_type));
+    };
+
+    RunResult {
+        outcome,
+        placements,
+        souls_left: (state.num_triangles, state.num_squares, state.num_circles),
+    }
+}
+
+struct SimulatedPlayer {
+    strategy: Strategy,
+    rng: ChaCha20Rng,
+    /// The rest of the solver's plan, last placement first
+    plan: Vec<Placement>,
+    /// Set once the solver has given up, so it isn't asked again every move
+    solver_gave_up: bool,
+}
+
+impl SimulatedPlayer {
+    fn next_move(&mut self, state: &GameState) -> Option<Placement> {
+        match self.strategy {
+            Strategy::Random => choose_move(state, OpponentDifficulty::Random, &mut self.rng),
+            Strategy::Greedy => choose_move(state, OpponentDifficulty::Greedy, &mut self.rng),
+            Strategy::Solver => {
+                if self.plan.is_empty() && !self.solver_gave_up {
+                    match solve(state, SOLVER_BUDGET) {
+                        Some(plan) => self.plan = plan.into_iter().rev().collect(),
+                        None => self.solver_gave_up = true,
+                    }
+                }
+
+                // replan if the next step isn't allowed any more, rather than trusting a stale plan
+                match self.plan.pop() {
+                    Some(placement) if valid_placements(state).contains(&placement) => {
+                        Some(placement)
+                    }
+                    _ => {
+                        self.plan.clear();
+                        choose_move(state, OpponentDifficulty::Greedy, &mut self.rng)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::GameEvent,
+        state::{
+            game_event_handler::StateEventHandler, parse_level_file, GameState, LevelFailure,
+            Piece, PieceType,
+        },
+        utils::tile_to_idx,
+    };
+
+    use super::{simulate, Outcome, Strategy};
+
+    fn tutorial() -> GameState {
+        let mut state = GameState::default();
+        state
+            .apply_event(GameEvent::LoadLevel { level_id: 0 })
+            .unwrap();
+        state
+    }
+
+    #[test]
+    fn test_solver_wins_the_tutorial() {
+        let report = simulate(&tutorial(), Strategy::Solver, 3, 7);
+        assert_eq!(report.win_rate(), 1.);
+
+        let summoned = [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman]
+            .into_iter()
+            .map(|piece_type| report.average_summoned(piece_type))
+            .sum::<f32>();
+        assert_eq!(summoned, report.average_placements());
+    }
+
+    #[test]
+    fn test_reports_are_repeatable() {
+        for strategy in Strategy::ALL {
+            let report = simulate(&tutorial(), strategy, 10, 42);
+            assert_eq!(report, simulate(&tutorial(), strategy, 10, 42));
+
+            assert_eq!(report.runs.len(), 10);
+            assert_eq!(report.outcome_rate(Outcome::GaveUp), 0., "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn test_simulating_an_edited_level_file() {
+        let level = GameState::LEVELS[0].replacen("0,1,1", "0,0,0", 1);
+        let state = GameState::from_level_data(parse_level_file(&level).unwrap());
+
+        let report = simulate(&state, Strategy::Greedy, 5, 1);
+        assert_eq!(
+            report.outcome_rate(Outcome::Failed(LevelFailure::OutOfSouls)),
+            1.
+        );
+        assert_eq!(report.average_placements(), 0.);
+    }
+
+    #[test]
+    fn test_versus_games_can_be_lost() {
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadVersus).unwrap();
+
+        // player 2 to play, next to the last two hounds player 1 has
+        for piece in state.pieces.iter_mut() {
+            if matches!(piece, Piece::Player0(_)) {
+                *piece = Piece::Empty;
+            }
+        }
+        state.pieces[tile_to_idx(3, 6)] = Piece::Player0(PieceType::Hound);
+        state.pieces[tile_to_idx(4, 6)] = Piece::Player0(PieceType::Hound);
+        state.switch_turns();
+
+        let report = simulate(&state, Strategy::Greedy, 3, 3);
+        assert_eq!(report.outcome_rate(Outcome::Lost), 1.);
+        assert_eq!(report.average_placements(), 1.);
+    }
+
+    #[test]
+    fn test_strategy_names_round_trip() {
+        for strategy in Strategy::ALL {
+            assert_eq!(Strategy::from_name(strategy.name()), Some(strategy));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! A depth first search for a sequence of placements that clears a level.
+//! Used to make sure generated levels can actually be won.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{state::PieceType, utils::idx_to_tile};
+
+use super::{board::Board, rules::LevelRules, GameState};
+
+/// A placement of a player piece at a tile x/y
+pub type Placement = (usize, usize, PieceType);
+
+/// Searches for a sequence of placements that wins the level, giving up after
+/// visiting `max_nodes` positions.
+pub fn solve(state: &GameState, max_nodes: usize) -> Option<Vec<Placement>> {
+    let mut search = Search {
+        visited: HashSet::new(),
+        max_nodes,
+        rules: &state.rules,
+    };
+
+    let mut solution = vec![];
+    if search.visit(state.board, &mut solution) {
+        Some(solution)
+    } else {
+        None
+    }
+}
+
+/// Searches breadth first for the fewest placements that win the level, giving up
+/// after visiting `max_nodes` positions.
+pub fn shortest_solution(state: &GameState, max_nodes: usize) -> Option<Vec<Placement>> {
+    let rules = &state.rules;
+    if state.board.is_level_over(rules) {
+        return Some(vec![]);
+    }
+
+    // every position seen so far, with the position and placement that first reached it
+    let mut visited = HashMap::from([(state.board, None)]);
+    let mut queue = VecDeque::from([state.board]);
+
+    while let Some(board) = queue.pop_front() {
+        for placement @ (x, y, piece_type) in valid_placements(&board) {
+            let mut child = board;
+            child.place_piece(x, y, piece_type, rules);
+            if visited.contains_key(&child) {
+                continue;
+            }
+            visited.insert(child, Some((board, placement)));
+
+            if child.is_level_over(rules) {
+                let mut solution = vec![];
+                let mut at = child;
+                while let Some(&Some((parent, placement))) = visited.get(&at) {
+                    solution.push(placement);
+                    at = parent;
+                }
+                solution.reverse();
+                return Some(solution);
+            }
+
+            if visited.len() >= max_nodes {
+                return None;
+            }
+            queue.push_back(child);
+        }
+    }
+
+    None
+}
+
+/// Gets every placement the board allows
+pub fn valid_placements(board: &Board) -> Vec<Placement> {
+    let mut result = vec![];
+
+    for piece_type in [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman] {
+        if !board.has_capacity(piece_type) {
+            continue;
+        }
+
+        for idx in 0..board.pieces.len() {
+            let (x, y) = idx_to_tile(idx);
+            if board.is_valid_placement_position(x, y) {
+                result.push((x, y, piece_type));
+            }
+        }
+    }
+
+    result
+}
+
+struct Search<'a> {
+    visited: HashSet<Board>,
+    max_nodes: usize,
+    rules: &'a LevelRules,
+}
+
+impl Search<'_> {
+    fn visit(&mut self, board: Board, solution: &mut Vec<Placement>) -> bool {
+        if board.is_level_over(self.rules) {
+            return true;
+        }
+
+        if self.visited.len() >= self.max_nodes {
+            return false;
+        }
+
+        if !self.visited.insert(board) {
+            return false;
+        }
+
+        // try the placements that harvest the most red souls first
+        let mut children = valid_placements(&board)
+            .into_iter()
+            .map(|(x, y, piece_type)| {
+                let mut child = board;
+                child.place_piece(x, y, piece_type, self.rules);
+                ((x, y, piece_type), child)
+            })
+            .collect::<Vec<_>>();
+        children.sort_by_key(|(_, child)| child.count_red_cells());
+
+        for (placement, child) in children {
+            solution.push(placement);
+            if self.visit(child, solution) {
+                return true;
+            }
+            solution.pop();
+        }
+
+        false
+    }
+}
@@ -0,0 +1,237 @@
+//! Local two player games. Players take turns placing pieces on the same board,
+//! each with their own soul pool. Matches harvest the other player's pieces into the
+//! pool of whoever made the match, and a player wins when the other has no pieces left.
+//! A player with nowhere to summon misses their turn, and if neither player can summon
+//! the game is a draw.
+
+use crate::event::GameEvent;
+
+use super::{board::Board, game_event_handler::StateEventHandler, GameMode, GameState, Player};
+
+/// How a finished versus game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersusResult {
+    Won(Player),
+    Draw,
+}
+
+impl Board {
+    /// Hands the turn to the other player, swapping in their soul pool
+    pub(super) fn switch_turns(&mut self) {
+        let current = (self.num_triangles, self.num_squares, self.num_circles);
+        (self.num_triangles, self.num_squares, self.num_circles) = self.waiting_souls;
+        self.waiting_souls = current;
+
+        self.current_player = self.current_player.other();
+    }
+
+    /// Hands the turn over after a placement, unless the other player has nowhere
+    /// to summon, in which case they miss their turn
+    pub(super) fn end_turn(&mut self) {
+        self.switch_turns();
+        if self.stuck_reason().is_some() {
+            self.switch_turns();
+        }
+    }
+
+    /// How a versus game on this board has ended, if it has
+    pub fn versus_result(&self) -> Option<VersusResult> {
+        let has_pieces = |player: Player| self.pieces.iter().any(|piece| player.owns(*piece));
+
+        match (has_pieces(Player::Player0), has_pieces(Player::Player1)) {
+            (true, false) => Some(VersusResult::Won(Player::Player0)),
+            (false, true) => Some(VersusResult::Won(Player::Player1)),
+            (false, false) => Some(VersusResult::Draw),
+            (true, true) if self.is_deadlocked() => Some(VersusResult::Draw),
+            (true, true) => None,
+        }
+    }
+
+    /// True if neither player has anywhere to summon
+    fn is_deadlocked(&self) -> bool {
+        let mut other = *self;
+        other.switch_turns();
+
+        self.stuck_reason().is_some() && other.stuck_reason().is_some()
+    }
+}
+
+impl GameState {
+    /// How the versus game ended, once it is over
+    pub fn versus_result(&self) -> Option<VersusResult> {
+        if self.mode == GameMode::Versus {
+            self.board.versus_result()
+        } else {
+            None
+        }
+    }
+
+    /// The winner of a versus game, once the other player has no pieces left
+    pub fn versus_winner(&self) -> Option<Player> {
+        match self.versus_result() {
+            Some(VersusResult::Won(winner)) => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// How many `Undo`s it takes to remove the player's last placement and any made after
+    /// it, so it is their turn again. None if there is nothing of theirs to undo.
+    pub fn undos_to_take_back(&self, player: Player) -> Option<usize> {
+        let mut state = self.clone();
+        let mut count = 0;
+        loop {
+            state.apply_event(GameEvent::Undo).ok()?;
+            count += 1;
+
+            // undoing leaves the turn with whoever made the placement
+            if state.current_player == player {
+                return Some(count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::{EventRejection, GameEvent},
+        state::{
+            game_event_handler::StateEventHandler, side_effects::SideEffect, GameState, Piece,
+            PieceType, Player,
+        },
+        utils::tile_to_idx,
+    };
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::state::opponent::{choose_move, OpponentDifficulty};
+
+    use super::VersusResult;
+
+    fn place(state: &mut GameState, x: usize, y: usize, piece_type: PieceType) -> Vec<SideEffect> {
+        state
+            .apply_event(GameEvent::PlacePlayerPiece { x, y, piece_type })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_players_take_turns_with_their_own_souls() {
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadVersus).unwrap();
+        assert_eq!(state.current_player, Player::Player0);
+
+        place(&mut state, 2, 1, PieceType::Hound);
+        assert_eq!(state.current_player, Player::Player1);
+        assert_eq!(state.num_circles, 3, "player 2 hasn't used any souls yet");
+        assert_eq!(state.waiting_souls.2, 2);
+
+        // player 2 can only summon next to their own pieces
+        assert_eq!(
+            state
+                .apply_event(GameEvent::PlacePlayerPiece {
+                    x: 2,
+                    y: 1,
+                    piece_type: PieceType::Hound
+                })
+                .unwrap_err(),
+            EventRejection::TileOccupied
+        );
+        assert_eq!(
+            state.check_placement(1, 1),
+            Err(EventRejection::NoAdjacentFriend)
+        );
+        assert_eq!(state.current_player, Player::Player1);
+        place(&mut state, 2, 6, PieceType::Hound);
+        assert_eq!(
+            state.pieces[tile_to_idx(2, 6)],
+            Piece::Player1(PieceType::Hound)
+        );
+        assert_eq!(state.current_player, Player::Player0);
+    }
+
+    #[test]
+    fn test_matches_harvest_the_other_players_pieces() {
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadVersus).unwrap();
+
+        // leave player 2 with a single piece that player 1 can harvest
+        for piece in state.pieces.iter_mut() {
+            if matches!(piece, Piece::Player1(_)) {
+                *piece = Piece::Empty;
+            }
+        }
+        state.pieces[tile_to_idx(3, 1)] = Piece::Player1(PieceType::Hound);
+        state.pieces[tile_to_idx(4, 1)] = Piece::Player0(PieceType::Hound);
+
+        let side_effects = place(&mut state, 2, 1, PieceType::Hound);
+
+        assert_eq!(state.versus_winner(), Some(Player::Player0));
+        assert_eq!(state.waiting_souls.2, 3, "player 1 harvested a hound");
+        assert!(matches!(
+            side_effects.last(),
+            Some(SideEffect::GameOver {
+                load_another: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_a_stuck_player_misses_their_turn() {
+        let mut state = GameState::default();
+        state.apply_event(GameEvent::LoadVersus).unwrap();
+
+        // player 2 still has pieces but no souls to summon with
+        state.waiting_souls = (0, 0, 0);
+        place(&mut state, 2, 1, PieceType::Hound);
+
+        assert_eq!(state.versus_result(), None);
+        assert_eq!(state.current_player, Player::Player0);
+        assert_eq!(state.num_circles, 2, "still player 1's souls");
+
+        // once player 1 runs out too, nobody can win
+        state.num_triangles = 0;
+        state.num_squares = 0;
+        state.num_circles = 0;
+        assert_eq!(state.versus_result(), Some(VersusResult::Draw));
+        assert!(state.is_level_over());
+        assert_eq!(state.versus_winner(), None);
+    }
+
+    #[test]
+    fn test_undoing_past_a_missed_turn() {
+        let mut checked_pass = false;
+
+        // play random games until player 2 misses a turn
+        for seed in 0..100 {
+            let mut state = GameState::default();
+            state.apply_event(GameEvent::LoadVersus).unwrap();
+            assert_eq!(state.undos_to_take_back(Player::Player0), None);
+
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            while let Some((x, y, piece_type)) =
+                choose_move(&state, OpponentDifficulty::Random, &mut rng)
+            {
+                let before = state.current_player;
+                place(&mut state, x, y, piece_type);
+                if state.is_level_over() {
+                    break;
+                }
+
+                if before == Player::Player0 && state.current_player == Player::Player0 {
+                    // only player 1's placement comes back
+                    assert_eq!(state.undos_to_take_back(Player::Player0), Some(1));
+                    checked_pass = true;
+                } else if state.current_player == Player::Player0 {
+                    // player 2's reply and player 1's placement before it
+                    assert_eq!(state.undos_to_take_back(Player::Player0), Some(2));
+                }
+            }
+
+            if checked_pass {
+                return;
+            }
+        }
+        panic!("player 2 never missed a turn");
+    }
+}
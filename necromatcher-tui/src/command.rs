@@ -0,0 +1,133 @@
+//! The text commands accepted in plain mode, one per line
+
+use necromatcher_core::{event::GameEvent, state::PieceType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Summon a piece at the given tile, using the selected piece type if none is given
+    Place {
+        x: usize,
+        y: usize,
+        piece_type: Option<PieceType>,
+    },
+    /// Pick the piece type to summon next
+    Select(PieceType),
+    /// Undo, reset or move on to the next level
+    Event(GameEvent),
+    /// Draw the board again
+    Show,
+    /// List the commands
+    Help,
+    Quit,
+}
+
+pub const HELP: &str = "\
+Commands:
+  x y [piece]       summon at column x and row y, counting from 0 at the bottom left
+  swordsman, s      summon swordsmen next, likewise hound/h and bowman/b
+  undo, u           take back the last summon
+  reset, r          restart the level
+  next, n           skip to the next level
+  show              draw the board again
+  help              show this list
+  quit, q           leave the game";
+
+impl Command {
+    /// Parses a line, returning None for blank lines and comments starting with `#`
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let words = line
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>();
+
+        let command = match words.as_slice() {
+            [] => return Ok(None),
+            ["undo" | "u"] => Self::Event(GameEvent::Undo),
+            ["reset" | "r"] => Self::Event(GameEvent::Reset),
+            ["next" | "n"] => Self::Event(GameEvent::NextLevel),
+            ["show"] => Self::Show,
+            ["help" | "?"] => Self::Help,
+            ["quit" | "q" | "exit"] => Self::Quit,
+            [piece] if parse_piece_type(piece).is_some() => {
+                Self::Select(parse_piece_type(piece).unwrap())
+            }
+            ["place", x, y, rest @ ..] | [x, y, rest @ ..] => {
+                let piece_type = match rest {
+                    [] => None,
+                    [piece] => Some(parse_piece_type(piece).ok_or_else(|| {
+                        format!("Unknown piece {piece}, try swordsman, hound or bowman")
+                    })?),
+                    _ => return Err(format!("Expected x y [piece], found {line}")),
+                };
+
+                Self::Place {
+                    x: parse_coord(x)?,
+                    y: parse_coord(y)?,
+                    piece_type,
+                }
+            }
+            _ => return Err(format!("Unknown command {}, try help", line.trim())),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+/// Parses the full or one letter name of a summonable piece type
+pub fn parse_piece_type(name: &str) -> Option<PieceType> {
+    match name.to_lowercase().as_str() {
+        "swordsman" | "s" => Some(PieceType::Swordsman),
+        "hound" | "h" => Some(PieceType::Hound),
+        "bowman" | "b" => Some(PieceType::Bowman),
+        _ => None,
+    }
+}
+
+fn parse_coord(value: &str) -> Result<usize, String> {
+    value
+        .trim_matches(',')
+        .parse()
+        .map_err(|_| format!("Expected a tile coordinate, found {value}"))
+}
+
+#[cfg(test)]
+mod test {
+    use necromatcher_core::{event::GameEvent, state::PieceType};
+
+    use super::Command;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            Command::parse("3 4"),
+            Ok(Some(Command::Place {
+                x: 3,
+                y: 4,
+                piece_type: None
+            }))
+        );
+        assert_eq!(
+            Command::parse("place 3, 4 hound # next to the swordsman"),
+            Ok(Some(Command::Place {
+                x: 3,
+                y: 4,
+                piece_type: Some(PieceType::Hound)
+            }))
+        );
+        assert_eq!(
+            Command::parse("b"),
+            Ok(Some(Command::Select(PieceType::Bowman)))
+        );
+        assert!(matches!(
+            Command::parse(" undo "),
+            Ok(Some(Command::Event(GameEvent::Undo)))
+        ));
+        assert_eq!(Command::parse("  # just a comment"), Ok(None));
+
+        assert!(Command::parse("3 four").is_err());
+        assert!(Command::parse("3 4 wall").is_err());
+        assert!(Command::parse("dance").is_err());
+    }
+}
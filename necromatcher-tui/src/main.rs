@@ -0,0 +1,367 @@
+//! Plays necromatcher in a terminal, using the same rules engine as the game.
+//!
+//! In a terminal the cursor keys move around the board and enter summons, or type a
+//! column then a row to summon there. When input is piped in, or with `--plain`, it
+//! reads one command per line instead and prints the board after each, which is handy
+//! for playtesting levels over SSH and for scripts. Run with `--help` for the options.
+
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal, Write},
+    process::ExitCode,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDate;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    terminal::{self, ClearType},
+};
+use necromatcher_core::{
+    event::GameEvent,
+    state::{daily::today, level_generator::endless_board_size},
+    COLS, ROWS,
+};
+use rand::{thread_rng, RngCore};
+
+use self::{
+    command::{parse_piece_type, Command, HELP},
+    render::render,
+    session::Session,
+};
+
+mod command;
+mod render;
+mod session;
+
+const USAGE: &str = "\
+Usage: necromatcher-tui [--level N | --endless [SEED] | --daily [YYYY-MM-DD] | --versus] [--plain]
+
+  --level N      start the campaign at level N, from 1
+  --endless      play generated levels, optionally from the given seed
+  --daily        play the daily challenge, today's unless a date is given
+  --versus       two players take turns on the same board
+  --plain        read commands line by line even in a terminal";
+
+/// How often the level timer is updated while waiting for a key
+const TICK: Duration = Duration::from_millis(250);
+
+struct Options {
+    load: GameEvent,
+    plain: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        load: GameEvent::LoadLevel { level_id: 0 },
+        plain: false,
+    };
+
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        // the value after a flag, if it isn't another flag
+        let mut value = || args.next_if(|next| !next.starts_with("--"));
+
+        match arg.as_str() {
+            "--level" => {
+                let level = value()
+                    .and_then(|level| level.parse::<usize>().ok())
+                    .filter(|level| *level > 0)
+                    .ok_or("--level needs a level number, starting from 1")?;
+                options.load = GameEvent::LoadLevel {
+                    level_id: level - 1,
+                };
+            }
+            "--endless" => {
+                let seed = match value() {
+                    Some(seed) => seed.parse().map_err(|_| format!("Bad seed {seed}"))?,
+                    None => thread_rng().next_u64(),
+                };
+                let difficulty = 1;
+                let (width, height) = endless_board_size(difficulty);
+                options.load = GameEvent::GenerateLevel {
+                    seed,
+                    width,
+                    height,
+                    difficulty,
+                };
+            }
+            "--daily" => {
+                let date = match value() {
+                    Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                        .map_err(|_| format!("Bad date {date}, expected YYYY-MM-DD"))?,
+                    None => today(),
+                };
+                options.load = GameEvent::LoadDaily { date };
+            }
+            "--versus" => options.load = GameEvent::LoadVersus,
+            "--plain" => options.plain = true,
+            other => return Err(format!("Unknown option {other}")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}\n\n{HELP}");
+        return ExitCode::SUCCESS;
+    }
+
+    let result = parse_args(args).and_then(|options| {
+        let session = Session::new(options.load)?;
+
+        let result = if options.plain || !io::stdin().is_terminal() {
+            run_plain(session)
+        } else {
+            run_interactive(session)
+        };
+        result.map_err(|err| err.to_string())
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads a command per line, printing any messages and then the board
+fn run_plain(session: Session) -> io::Result<()> {
+    // read on another thread, so the level's clock keeps ticking while waiting for a line
+    let (send_line, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            if send_line.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    plain_loop(session, &lines, &mut io::stdout().lock())
+}
+
+fn plain_loop(
+    mut session: Session,
+    lines: &Receiver<io::Result<String>>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "{}\n", render(&session, None).join("\n"))?;
+
+    let mut last_tick = Instant::now();
+    loop {
+        let line = match lines.recv_timeout(TICK) {
+            Ok(line) => Some(line?),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut messages = session.tick(last_tick.elapsed().as_secs_f32());
+        last_tick = Instant::now();
+        // show the board when the clock runs out, as the player may be waiting on it
+        let mut redraw = !messages.is_empty();
+
+        // the board is drawn after everything but errors and help
+        match line.as_deref().map(Command::parse) {
+            None | Some(Ok(None)) => {}
+            Some(Ok(Some(command))) => {
+                messages.extend(session.run(command));
+                redraw |= command != Command::Help;
+            }
+            Some(Err(err)) => messages.push(err),
+        }
+        if session.quit {
+            break;
+        }
+
+        for message in messages {
+            writeln!(out, "{message}")?;
+        }
+        if redraw {
+            writeln!(out, "{}\n", render(&session, None).join("\n"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes over the terminal, redrawing the board after every key
+fn run_interactive(mut session: Session) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = interactive_loop(&mut session, &mut out);
+
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn interactive_loop(session: &mut Session, out: &mut impl Write) -> io::Result<()> {
+    let mut messages: Vec<String> = vec![];
+    // the column typed so far when entering a tile by number
+    let mut typed_column = None;
+    let mut last_tick = Instant::now();
+
+    while !session.quit {
+        let mut lines = render(session, Some(session.cursor));
+        lines.push(String::new());
+        lines.extend(messages.iter().flat_map(|m| m.lines().map(str::to_owned)));
+        lines.push(match typed_column {
+            Some(x) => format!("Column {x}, now type a row"),
+            None => {
+                "Arrows move, enter summons, s/h/b pick a creature, u undo, r reset, n next, q quit"
+                    .to_owned()
+            }
+        });
+
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        write!(out, "{}", lines.join("\r\n"))?;
+        out.flush()?;
+
+        let ticked = session.tick(last_tick.elapsed().as_secs_f32());
+        last_tick = Instant::now();
+        if !ticked.is_empty() {
+            messages = ticked;
+        }
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+
+        let (x, y) = session.cursor;
+        let command = match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Some(Command::Quit),
+            KeyCode::Left => {
+                session.cursor.0 = x.saturating_sub(1);
+                None
+            }
+            KeyCode::Right => {
+                session.cursor.0 = (x + 1).min(COLS - 1);
+                None
+            }
+            KeyCode::Down => {
+                session.cursor.1 = y.saturating_sub(1);
+                None
+            }
+            KeyCode::Up => {
+                session.cursor.1 = (y + 1).min(ROWS - 1);
+                None
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => Some(Command::Place {
+                x,
+                y,
+                piece_type: None,
+            }),
+            KeyCode::Char(digit @ '0'..='9') => {
+                let value = digit.to_digit(10).unwrap() as usize;
+                match typed_column.take() {
+                    None => {
+                        typed_column = Some(value);
+                        None
+                    }
+                    Some(column) => {
+                        session.cursor = (column.min(COLS - 1), value.min(ROWS - 1));
+                        Some(Command::Place {
+                            x: column,
+                            y: value,
+                            piece_type: None,
+                        })
+                    }
+                }
+            }
+            KeyCode::Esc => {
+                typed_column = None;
+                None
+            }
+            KeyCode::Char(key) => match key {
+                'u' => Some(Command::Event(GameEvent::Undo)),
+                'r' => Some(Command::Event(GameEvent::Reset)),
+                'n' => Some(Command::Event(GameEvent::NextLevel)),
+                'q' => Some(Command::Quit),
+                other => parse_piece_type(&other.to_string()).map(Command::Select),
+            },
+            _ => None,
+        };
+
+        if let Some(command) = command {
+            messages = session.run(command);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use necromatcher_core::{
+        event::GameEvent,
+        state::{parse_level_file, GameState},
+    };
+
+    use crate::session::Session;
+
+    use super::{parse_args, plain_loop};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args("--level 3 --plain")).unwrap();
+        assert_eq!(options.load, GameEvent::LoadLevel { level_id: 2 });
+        assert!(options.plain);
+
+        assert!(matches!(
+            parse_args(args("--endless 42")).unwrap().load,
+            GameEvent::GenerateLevel { seed: 42, .. }
+        ));
+        assert!(matches!(
+            parse_args(args("--daily --plain")).unwrap().load,
+            GameEvent::LoadDaily { .. }
+        ));
+
+        assert!(parse_args(args("--level 0")).is_err());
+        assert!(parse_args(args("--daily yesterday")).is_err());
+        assert!(parse_args(args("--fly")).is_err());
+    }
+
+    #[test]
+    fn test_plain_mode_runs_the_clock_without_input() {
+        let mut session = Session::new(GameEvent::LoadLevel { level_id: 0 }).unwrap();
+        let level = format!("{}\ntime_limit = 0.1", GameState::LEVELS[0].trim_end());
+        session.state = GameState::from_level_data(parse_level_file(&level).unwrap());
+
+        // nothing is typed until well after the time limit, and then the input ends
+        let (send_line, lines) = mpsc::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(600));
+            drop(send_line);
+        });
+
+        let mut out = vec![];
+        plain_loop(session, &lines, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("Out of time!"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Draws a game as lines of text. The player's pieces are upper case and the
+//! other side's are lower case, `S` for swordsmen, `H` for hounds, `B` for bowmen,
+//! with `#` for walls and `.` for empty tiles.
+
+use necromatcher_core::{
+    state::{daily::par_text, GameMode, GameState, Piece, PieceType},
+    utils::tile_to_idx,
+    COLS, ROWS,
+};
+
+use crate::session::Session;
+
+/// The character a piece is drawn with
+pub fn glyph(piece: Piece) -> char {
+    let letter = |piece_type| match piece_type {
+        PieceType::Swordsman => 'S',
+        PieceType::Hound => 'H',
+        PieceType::Bowman => 'B',
+        PieceType::Wall => '#',
+    };
+
+    match piece {
+        Piece::Empty => '.',
+        Piece::Obstacle(_) => '#',
+        Piece::Player0(piece_type) => letter(piece_type),
+        Piece::Player1(piece_type) => letter(piece_type).to_ascii_lowercase(),
+    }
+}
+
+/// Draws the board with the column and row numbers used by placement commands.
+/// The tile under the cursor is bracketed if there is one.
+pub fn render_board(state: &GameState, cursor: Option<(usize, usize)>) -> Vec<String> {
+    let mut lines = vec![];
+
+    for y in (0..ROWS).rev() {
+        let mut line = format!("{y} ");
+        for x in 0..COLS {
+            let glyph = glyph(state.pieces[tile_to_idx(x, y)]);
+            if cursor == Some((x, y)) {
+                line.push_str(&format!("[{glyph}]"));
+            } else {
+                line.push_str(&format!(" {glyph} "));
+            }
+        }
+        lines.push(line.trim_end().to_owned());
+    }
+
+    let columns = (0..COLS).map(|x| format!(" {x} ")).collect::<String>();
+    lines.push(format!("  {}", columns.trim_end()));
+
+    lines
+}
+
+/// Draws the level header, the board and what the player has left to summon
+pub fn render(session: &Session, cursor: Option<(usize, usize)>) -> Vec<String> {
+    let state = &session.state;
+    let mut lines = vec![title(state)];
+
+    if !state.level_message.is_empty() {
+        lines.push(state.level_message.clone());
+        lines.push(state.rules.objective.as_objective().describe(state));
+    }
+    lines.push(String::new());
+
+    lines.extend(render_board(state, cursor));
+    lines.push(String::new());
+
+    let summoning = |piece_type: PieceType, name: &str, count: usize| {
+        let marker = if piece_type == session.piece_type {
+            '>'
+        } else {
+            ' '
+        };
+        format!("{marker}{name} {count}")
+    };
+    lines.push(format!(
+        "Souls: {}  {}  {}",
+        summoning(PieceType::Swordsman, "[s]wordsman", state.num_squares),
+        summoning(PieceType::Hound, "[h]ound", state.num_circles),
+        summoning(PieceType::Bowman, "[b]owman", state.num_triangles),
+    ));
+
+    lines
+}
+
+fn title(state: &GameState) -> String {
+    let title = match state.mode {
+        GameMode::Campaign => format!("Level {}", state.get_current_level()),
+        GameMode::Endless { difficulty, .. } => format!(
+            "Endless - Level {} (difficulty {difficulty})",
+            state.get_current_level()
+        ),
+        GameMode::Daily { date, par } => format!(
+            "Daily {date} - {} moves (par {})",
+            state.moves_this_level(),
+            par_text(par)
+        ),
+        GameMode::Versus => format!("Versus - {}'s turn", state.current_player.name()),
+    };
+
+    let mut limits = String::new();
+    if let Some(moves) = state.moves_remaining() {
+        limits.push_str(&format!(" - {moves} moves left"));
+    }
+    if let Some(seconds) = state.time_remaining() {
+        limits.push_str(&format!(" - {:.0}s left", seconds.ceil()));
+    }
+
+    format!("{title}{limits}")
+}
+
+#[cfg(test)]
+mod test {
+    use necromatcher_core::event::GameEvent;
+
+    use crate::session::Session;
+
+    use super::render_board;
+
+    #[test]
+    fn test_render_tutorial() {
+        let session = Session::new(GameEvent::LoadLevel { level_id: 0 }).unwrap();
+
+        assert_eq!(
+            render_board(&session.state, Some((0, 0))).join("\n"),
+            "\
+7  .  .  .  .  .  .  .  .
+6  .  .  .  .  .  .  .  .
+5  .  .  h  .  .  .  .  .
+4  .  .  h  s  .  .  .  .
+3  .  .  S  .  s  .  .  .
+2  .  .  H  s  .  .  .  .
+1  .  .  .  .  .  .  .  .
+0 [.] .  .  .  .  .  .  .
+   0  1  2  3  4  5  6  7"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! A game being played in the terminal. Applies events to the [GameState] and turns
+//! the side effects the game would animate into messages for the player.
+
+use necromatcher_core::{
+    event::GameEvent,
+    state::{
+        game_event_handler::StateEventHandler, side_effects::SideEffect, versus::VersusResult,
+        GameMode, GameState, LevelFailure, PieceType,
+    },
+    COLS, ROWS,
+};
+
+use crate::command::{Command, HELP};
+
+pub struct Session {
+    pub state: GameState,
+    /// The type of piece summoned when a placement doesn't name one
+    pub piece_type: PieceType,
+    /// The tile picked with the cursor keys
+    pub cursor: (usize, usize),
+    /// Set once a game without another level to load has finished, until it is reset
+    pub game_over: bool,
+    pub quit: bool,
+}
+
+impl Session {
+    /// Starts a game by applying the event that loads its first level
+    pub fn new(load: GameEvent) -> Result<Self, String> {
+        let mut session = Self {
+            state: GameState::default(),
+            piece_type: PieceType::default(),
+            cursor: (COLS / 2, ROWS / 2),
+            game_over: false,
+            quit: false,
+        };
+
+        session
+            .state
+            .apply_event(load)
+            .map_err(|rejection| rejection.to_string())?;
+
+        Ok(session)
+    }
+
+    /// Runs a command, returning any messages for the player
+    pub fn run(&mut self, command: Command) -> Vec<String> {
+        match command {
+            Command::Place { x, y, piece_type } => {
+                if self.game_over {
+                    return vec!["The game is over, reset to play again".to_owned()];
+                }
+
+                let piece_type = piece_type.unwrap_or(self.piece_type);
+                self.apply(GameEvent::PlacePlayerPiece { x, y, piece_type })
+            }
+            Command::Select(piece_type) => {
+                self.piece_type = piece_type;
+                vec![]
+            }
+            Command::Event(event) => self.apply(event),
+            Command::Show => vec![],
+            Command::Help => vec![HELP.to_owned()],
+            Command::Quit => {
+                self.quit = true;
+                vec![]
+            }
+        }
+    }
+
+    /// Counts down the level's time limit
+    pub fn tick(&mut self, delta_seconds: f32) -> Vec<String> {
+        let side_effects = self.state.advance_time(delta_seconds);
+        self.handle(side_effects)
+    }
+
+    fn apply(&mut self, event: GameEvent) -> Vec<String> {
+        match self.state.apply_event(event) {
+            Ok(side_effects) => self.handle(side_effects),
+            Err(rejection) => vec![rejection.to_string()],
+        }
+    }
+
+    fn handle(&mut self, side_effects: Vec<SideEffect>) -> Vec<String> {
+        let mut messages = vec![];
+
+        for side_effect in side_effects {
+            match side_effect {
+                SideEffect::GameOver { load_another: true } => {
+                    messages.push("Level complete!".to_owned());
+                    messages.extend(self.apply(GameEvent::NextLevel));
+                }
+                SideEffect::GameOver {
+                    load_another: false,
+                } => {
+                    self.game_over = true;
+                    messages.push(self.game_over_message());
+                }
+                SideEffect::RemoveGameOverCondition => self.game_over = false,
+                SideEffect::LevelFailed { reason } => messages.push(failure_message(reason)),
+                _ => {}
+            }
+        }
+
+        messages
+    }
+
+    fn game_over_message(&self) -> String {
+        if let Some(result) = self.state.daily_result() {
+            return format!("Daily challenge complete!\n{}", result.share_text());
+        }
+
+        match self.state.versus_result() {
+            Some(VersusResult::Won(winner)) => {
+                return format!("{} wins! Reset to play again.", winner.name())
+            }
+            Some(VersusResult::Draw) => return "It's a draw! Reset to play again.".to_owned(),
+            None => {}
+        }
+
+        match self.state.mode {
+            GameMode::Campaign => "You beat every level! Reset to play again.".to_owned(),
+            _ => "Game over! Reset to play again.".to_owned(),
+        }
+    }
+}
+
+fn failure_message(reason: LevelFailure) -> String {
+    let retry = "Reset to try again, or undo to take back a move.";
+
+    match reason {
+        LevelFailure::OutOfMoves => format!("Out of moves! {retry}"),
+        LevelFailure::OutOfTime => "Out of time! Reset to try again.".to_owned(),
+        LevelFailure::OutOfSouls => format!("You have no souls left to summon! {retry}"),
+        LevelFailure::NoValidPlacements => format!("There is nowhere left to summon! {retry}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use necromatcher_core::{
+        event::GameEvent,
+        state::{solver::solve, PieceType},
+    };
+
+    use crate::command::Command;
+
+    use super::Session;
+
+    #[test]
+    fn test_solving_a_level_moves_on_to_the_next() {
+        let mut session = Session::new(GameEvent::LoadLevel { level_id: 0 }).unwrap();
+
+        let mut messages = vec![];
+        for (x, y, piece_type) in solve(&session.state, 100_000).unwrap() {
+            messages.extend(session.run(Command::Place {
+                x,
+                y,
+                piece_type: Some(piece_type),
+            }));
+        }
+
+        assert_eq!(messages, vec!["Level complete!"]);
+        assert_eq!(session.state.get_current_level(), 2);
+    }
+
+    #[test]
+    fn test_rejections_are_explained() {
+        let mut session = Session::new(GameEvent::LoadLevel { level_id: 0 }).unwrap();
+        session.run(Command::Select(PieceType::Hound));
+
+        assert_eq!(
+            session.run(Command::Place {
+                x: 7,
+                y: 7,
+                piece_type: None
+            }),
+            vec!["You can only summon next to your own pieces"]
+        );
+        assert_eq!(
+            session.run(Command::Event(GameEvent::Undo)),
+            vec!["There is nothing to undo"]
+        );
+    }
+}
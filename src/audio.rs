@@ -1,7 +1,14 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
-use crate::{loaders::AudioFiles, AppState};
+use crate::{loaders::AudioFiles, settings::Settings, AppState};
+
+/// Base mix volumes, these are scaled by the player's [Settings]
+pub const MUSIC_VOLUME: f64 = 0.15;
+pub const PLACE_VOLUME: f64 = 0.5;
+pub const LEVEL_COMPLETE_VOLUME: f64 = 0.2;
+pub const DESPAWN_VOLUME: f64 = 0.1;
+pub const DENIED_VOLUME: f64 = 0.3;
 
 pub struct InternalAudioPlugin;
 
@@ -12,9 +19,21 @@ impl Plugin for InternalAudioPlugin {
     }
 }
 
-fn spawn_background_music(audio: Res<Audio>, audio_files: Res<AudioFiles>) {
-    audio
+/// The instance of the looping background music, so its volume can be changed
+#[derive(Resource)]
+pub struct BackgroundMusic(pub Handle<AudioInstance>);
+
+fn spawn_background_music(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    audio_files: Res<AudioFiles>,
+    settings: Res<Settings>,
+) {
+    let instance = audio
         .play(audio_files.music.clone())
-        .with_volume(0.15)
-        .looped();
+        .with_volume(settings.music())
+        .looped()
+        .handle();
+
+    commands.insert_resource(BackgroundMusic(instance));
 }
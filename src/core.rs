@@ -6,28 +6,28 @@ use bevy::{
     prelude::*,
 };
 
+use rand::{thread_rng, RngCore};
+
 use crate::AppState;
 
 use self::{
     event::GameEvent,
     state::{
-        game_event_handler::state_mutation,
+        daily::today,
+        level_generator::endless_board_size,
         side_effects::{side_effect_handler, spawn_sprites_for_visualisations, SideEffect},
         GameState, PlayingPiece,
     },
+    systems::{advance_level_timer, computer_opponent_turn, state_mutation},
 };
 
+pub use necromatcher_core::{event, COLS, ROWS};
+
 pub(crate) mod colours;
-pub(crate) mod event;
 pub(crate) mod state;
+pub(crate) mod systems;
 pub(crate) mod utils;
 
-/// Number of rows in the grid
-pub const ROWS: usize = 8;
-
-/// Number of columns in the grid
-pub const COLS: usize = 8;
-
 /// The size of each grid square
 pub const GRID_SIZE: usize = 64;
 
@@ -38,6 +38,7 @@ impl Plugin for CorePlugin {
         app.insert_resource(ClearColor(Color::BLACK))
             .init_resource::<GameState>()
             .init_resource::<PlayingPiece>()
+            .init_resource::<GameModeSelection>()
             .add_event::<GameEvent>()
             .add_event::<SideEffect>()
             .add_systems(Startup, spawn_camera)
@@ -45,7 +46,11 @@ impl Plugin for CorePlugin {
             .add_systems(
                 Update,
                 (
-                    state_mutation,
+                    (
+                        advance_level_timer,
+                        computer_opponent_turn.before(state_mutation),
+                        state_mutation,
+                    ),
                     (side_effect_handler, spawn_sprites_for_visualisations).chain(),
                 )
                     .run_if(in_state(AppState::Game)),
@@ -53,6 +58,22 @@ impl Plugin for CorePlugin {
     }
 }
 
+/// The kind of game picked from the menu
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameModeSelection {
+    /// Play through the hand made levels
+    #[default]
+    Campaign,
+    /// Play randomly generated levels until you give up
+    Endless,
+    /// Play today's generated level
+    Daily,
+    /// Two people take turns on the same board
+    Versus,
+    /// Play a versus game against the computer
+    VersusComputer,
+}
+
 #[derive(Component)]
 pub struct MainCamera;
 
@@ -71,6 +92,62 @@ fn spawn_camera(mut commands: Commands) {
     ));
 }
 
-fn load_level(mut events: EventWriter<GameEvent>) {
-    events.send(GameEvent::LoadLevel { level_id: 0 });
+fn load_level(mode: Res<GameModeSelection>, mut events: EventWriter<GameEvent>) {
+    match *mode {
+        GameModeSelection::Campaign => {
+            events.send(GameEvent::LoadLevel { level_id: 0 });
+        }
+        GameModeSelection::Endless => {
+            let difficulty = 1;
+            let (width, height) = endless_board_size(difficulty);
+
+            events.send(GameEvent::GenerateLevel {
+                seed: thread_rng().next_u64(),
+                width,
+                height,
+                difficulty,
+            });
+        }
+        GameModeSelection::Daily => {
+            events.send(GameEvent::LoadDaily { date: today() });
+        }
+        GameModeSelection::Versus | GameModeSelection::VersusComputer => {
+            events.send(GameEvent::LoadVersus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{testing::TestApp, AppState};
+
+    use super::{event::GameEvent, state::solver::solve, GameModeSelection};
+
+    #[test]
+    fn test_starting_a_game_loads_the_first_level() {
+        let mut app = TestApp::new();
+        assert_eq!(app.app_state(), AppState::Menu);
+        assert!(app.pieces().is_empty());
+
+        app.start(GameModeSelection::Campaign);
+        assert_eq!(app.app_state(), AppState::Game);
+        assert_eq!(app.state().get_current_level(), 1);
+        app.assert_pieces_match_state();
+    }
+
+    #[test]
+    fn test_beating_a_level_loads_the_next() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Campaign);
+
+        let solution = solve(app.state(), 100_000).expect("the tutorial can be solved");
+        for (x, y, piece_type) in solution {
+            app.send(GameEvent::PlacePlayerPiece { x, y, piece_type });
+            app.advance(1.);
+        }
+
+        assert_eq!(app.state().get_current_level(), 2);
+        assert!(!app.state().is_level_over());
+        app.assert_pieces_match_state();
+    }
 }
@@ -3,6 +3,57 @@ use bevy::render::color::Color;
 pub const DEFAULT_GRID_BORDER: Color = Color::rgb_linear(0.01, 0.01, 0.01);
 pub const DEFAULT_GRID_HOVER_BORDER_VALID: Color = Color::rgb_linear(0.45, 1.35, 0.45);
 pub const DEFAULT_GRID_HOVER_BORDER_INVALID: Color = Color::rgb_linear(1.35, 0.45, 0.45);
+/// Marks the tile a level's objective wants the player to reach
+pub const OBJECTIVE_TILE_BORDER: Color = Color::rgb_linear(1.6, 1.4, 0.2);
+/// Ghosts the tiles that would be cleared by the placement being hovered
+pub const PREVIEW_CLEAR_BORDER: Color = Color::rgb_linear(0.9, 0.9, 1.4);
+pub const PREVIEW_CLEAR_FILL: Color = Color::rgba_linear(0.6, 0.6, 1.0, 0.12);
+/// Marks every tile the current creature can be summoned on while the move overlay is shown
+pub const VALID_MOVE_OVERLAY: Color = Color::rgba_linear(0.45, 1.35, 0.45, 0.5);
+/// Marks the tiles in the move overlay where a summon would make a match
+pub const MATCHING_MOVE_OVERLAY: Color = Color::rgba_linear(1.6, 1.4, 0.2, 0.8);
 
 pub const PLAYER_0_COLOUR: Color = Color::rgb_linear(0., 1.8, 0.3);
 pub const PLAYER_1_COLOUR: Color = Color::rgb_linear(2.8, 0., 0.3);
+
+/// Blue/orange alternatives to the red/green player colours for colourblind players
+pub const PLAYER_0_COLOURBLIND_COLOUR: Color = Color::rgb_linear(0.2, 0.7, 2.4);
+pub const PLAYER_1_COLOURBLIND_COLOUR: Color = Color::rgb_linear(2.6, 1.0, 0.);
+
+/// Blue/orange alternatives to the green/red valid and invalid placement colours
+pub const COLOURBLIND_GRID_HOVER_BORDER_VALID: Color = Color::rgb_linear(0.45, 0.9, 1.8);
+pub const COLOURBLIND_GRID_HOVER_BORDER_INVALID: Color = Color::rgb_linear(1.8, 0.7, 0.1);
+pub const COLOURBLIND_VALID_MOVE_OVERLAY: Color = Color::rgba_linear(0.45, 0.9, 1.8, 0.5);
+
+/// Gets the (player 0, player 1) colours for the selected palette
+pub fn player_colours(colourblind: bool) -> (Color, Color) {
+    if colourblind {
+        (PLAYER_0_COLOURBLIND_COLOUR, PLAYER_1_COLOURBLIND_COLOUR)
+    } else {
+        (PLAYER_0_COLOUR, PLAYER_1_COLOUR)
+    }
+}
+
+/// Gets the (valid, invalid) hover border colours for the selected palette
+pub fn hover_border_colours(colourblind: bool) -> (Color, Color) {
+    if colourblind {
+        (
+            COLOURBLIND_GRID_HOVER_BORDER_VALID,
+            COLOURBLIND_GRID_HOVER_BORDER_INVALID,
+        )
+    } else {
+        (
+            DEFAULT_GRID_HOVER_BORDER_VALID,
+            DEFAULT_GRID_HOVER_BORDER_INVALID,
+        )
+    }
+}
+
+/// Gets the move overlay colour for the selected palette
+pub fn valid_move_overlay(colourblind: bool) -> Color {
+    if colourblind {
+        COLOURBLIND_VALID_MOVE_OVERLAY
+    } else {
+        VALID_MOVE_OVERLAY
+    }
+}
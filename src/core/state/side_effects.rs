@@ -1,47 +1,33 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_kira_audio::{Audio, AudioControl};
 
 use crate::{
     animation::{AnimationIndices, AnimationTimer},
+    audio::{DENIED_VOLUME, LEVEL_COMPLETE_VOLUME, PLACE_VOLUME},
     core::{
-        colours::{PLAYER_0_COLOUR, PLAYER_1_COLOUR},
+        colours::player_colours,
         event::GameEvent,
-        state::{game_event_handler::DEFAULT_DESPAWN_DELAY, level_loader::NUM_LEVELS},
+        state::game_event_handler::DEFAULT_DESPAWN_DELAY,
         utils::{idx_to_tile, tile_coords},
     },
-    graphics::piece_visualisation::{DespawnItem, GamePieceVisualisation},
+    graphics::piece_visualisation::{DespawnItem, FallingPiece, GamePieceVisualisation},
+    input::actions::Action,
     loaders::{AudioFiles, SpritesheetFiles},
+    settings::Settings,
 };
 
-use super::{GameState, Obstacle, PieceType};
+pub use necromatcher_core::state::side_effects::SideEffect;
+
+use super::{GameState, LevelFailure, Obstacle, PieceType};
 
 /// Spawned when the game is over, dude
 #[derive(Component)]
 pub struct GameOverDude;
 
-#[derive(Event, Debug, Clone, Copy)]
-pub enum SideEffect {
-    /// Spawn a visual entity at the given tile. Includes the piece
-    /// type because it may immediately be despawned in the game state
-    SpawnAtTile {
-        idx: usize,
-        piece_type: PieceType,
-        is_player_owned: bool,
-        also_destroy: bool,
-    },
-    /// Despawn the visual entity at the given tile after a delay
-    DespawnAtTile { idx: usize, delay: f32 },
-    /// Destroy all visual tiles and respawn them
-    FullRespawnTiles,
-    /// The game is over
-    GameOver { load_another: bool },
-    /// Undo the game over state
-    RemoveGameOverCondition,
-}
-
 pub fn spawn_sprites_for_visualisations(
     mut commands: Commands,
     spritesheets: Res<SpritesheetFiles>,
+    settings: Res<Settings>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     added: Query<(Entity, &GamePieceVisualisation), Added<GamePieceVisualisation>>,
 ) {
@@ -49,6 +35,8 @@ pub fn spawn_sprites_for_visualisations(
         return;
     }
 
+    let (player_0_colour, player_1_colour) = player_colours(settings.colourblind);
+
     let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 13, 1, None, None);
     let layout = texture_atlas_layouts.add(layout);
 
@@ -79,9 +67,9 @@ pub fn spawn_sprites_for_visualisations(
                     color: if first == 12 {
                         Color::WHITE
                     } else if vis.is_player_owned {
-                        PLAYER_0_COLOUR
+                        player_0_colour
                     } else {
-                        PLAYER_1_COLOUR
+                        player_1_colour
                     },
                     ..default()
                 },
@@ -104,10 +92,19 @@ pub fn side_effect_handler(
     time: Res<Time>,
     audio: Res<Audio>,
     audio_files: Res<AudioFiles>,
+    settings: Res<Settings>,
     mut state: ResMut<GameState>,
     game_overs: Query<Entity, With<GameOverDude>>,
-    piece_query: Query<(Entity, &GamePieceVisualisation)>,
+    piece_query: Query<(Entity, &GamePieceVisualisation, Has<DespawnItem>)>,
 ) {
+    // the piece on each tile, kept up to date as side effects are handled because
+    // spawns and moves from earlier side effects aren't applied until later
+    let mut pieces = piece_query
+        .iter()
+        .filter(|(_, _, despawning)| !despawning)
+        .map(|(entity, vis, _)| (vis.idx, (entity, *vis)))
+        .collect::<HashMap<_, _>>();
+
     for side_effect in events.read() {
         info!("Handling side effect: {side_effect:?}");
 
@@ -118,11 +115,14 @@ pub fn side_effect_handler(
                 is_player_owned,
                 also_destroy,
             } => {
-                spawn_game_piece(
+                let vis = GamePieceVisualisation {
+                    idx: *idx,
+                    piece_type: *piece_type,
+                    is_player_owned: *is_player_owned,
+                };
+                let entity = spawn_game_piece(
                     &mut commands,
-                    *idx,
-                    *piece_type,
-                    *is_player_owned,
+                    vis,
                     if *also_destroy {
                         Some(time.elapsed_seconds() + DEFAULT_DESPAWN_DELAY)
                     } else {
@@ -130,58 +130,125 @@ pub fn side_effect_handler(
                     },
                 );
 
-                audio.play(audio_files.place.clone()).with_volume(0.5);
+                if !also_destroy {
+                    pieces.insert(*idx, (entity, vis));
+                }
+
+                audio
+                    .play(audio_files.place.clone())
+                    .with_volume(settings.sfx(PLACE_VOLUME));
             }
             SideEffect::DespawnAtTile { idx, delay } => {
-                // maybe a bit inefficient but again idc
-                for (entity, piece) in piece_query.iter() {
-                    if piece.idx == *idx {
-                        commands.entity(entity).insert(DespawnItem {
-                            despawn_time: time.elapsed_seconds() + delay,
-                        });
-
-                        break;
-                    }
+                if let Some((entity, _)) = pieces.remove(idx) {
+                    commands.entity(entity).insert(DespawnItem {
+                        despawn_time: time.elapsed_seconds() + delay,
+                    });
                 }
             }
+            SideEffect::MoveTile { from, to, delay } => {
+                if let Some((entity, mut vis)) = pieces.remove(from) {
+                    vis.idx = *to;
+                    commands.entity(entity).insert((
+                        vis,
+                        FallingPiece {
+                            from: tile_position(*from),
+                            to: tile_position(*to),
+                            start_time: time.elapsed_seconds() + delay,
+                            hidden_until_start: false,
+                        },
+                    ));
+                    pieces.insert(*to, (entity, vis));
+                }
+            }
+            SideEffect::DropInAtTile {
+                idx,
+                piece_type,
+                rows,
+                delay,
+            } => {
+                let vis = GamePieceVisualisation {
+                    idx: *idx,
+                    piece_type: *piece_type,
+                    is_player_owned: false,
+                };
+                let entity = spawn_game_piece(&mut commands, vis, None);
+
+                let (x, y) = idx_to_tile(*idx);
+                commands.entity(entity).insert(FallingPiece {
+                    from: tile_coords(x, y + rows).min,
+                    to: tile_position(*idx),
+                    start_time: time.elapsed_seconds() + delay,
+                    hidden_until_start: true,
+                });
+                pieces.insert(*idx, (entity, vis));
+            }
             SideEffect::FullRespawnTiles => {
-                for (entity, _) in piece_query.iter() {
+                for (entity, _, _) in piece_query.iter() {
                     commands.entity(entity).despawn();
                 }
+                pieces.clear();
 
-                for tile in state.tiles {
-                    match tile.piece {
-                        super::Piece::Empty => {}
-                        super::Piece::Player0(pt) => {
-                            spawn_game_piece(&mut commands, tile.idx(), pt, true, None);
-                        }
-                        super::Piece::Player1(pt) => {
-                            spawn_game_piece(&mut commands, tile.idx(), pt, false, None);
-                        }
+                for (idx, piece) in state.pieces.iter().enumerate() {
+                    let (piece_type, is_player_owned) = match *piece {
+                        super::Piece::Empty => continue,
+                        super::Piece::Player0(pt) => (pt, true),
+                        super::Piece::Player1(pt) => (pt, false),
                         super::Piece::Obstacle(pt) => {
-                            spawn_obstacle(&mut commands, tile.idx(), pt);
+                            spawn_obstacle(&mut commands, idx, pt);
+                            continue;
                         }
-                    }
+                    };
+
+                    let vis = GamePieceVisualisation {
+                        idx,
+                        piece_type,
+                        is_player_owned,
+                    };
+                    let entity = spawn_game_piece(&mut commands, vis, None);
+                    pieces.insert(idx, (entity, vis));
                 }
             }
             SideEffect::GameOver { load_another } => {
                 state.level_message = String::new();
 
-                if *load_another && state.current_level < NUM_LEVELS {
+                if *load_another {
                     audio
                         .play(audio_files.level_complete.clone())
-                        .with_volume(0.2);
+                        .with_volume(settings.sfx(LEVEL_COMPLETE_VOLUME));
                     game_events.send(GameEvent::NextLevel);
                 } else {
                     warn!("I think thats game over, probably should implement something");
                     commands.spawn(GameOverDude);
-                    state.current_level += 1; // increment here so we know reset should go back to level 1
 
-                    for (entity, _) in piece_query.iter() {
+                    for (entity, _, _) in piece_query.iter() {
                         commands.entity(entity).despawn();
                     }
+                    pieces.clear();
                 }
             }
+            SideEffect::LevelFailed { reason } => {
+                warn!("Level failed - {reason:?}");
+
+                let reset = settings.bindings.describe(Action::Reset);
+                let undo = settings.bindings.describe(Action::Undo);
+                let retry = format!("Press {reset} to try again, or {undo} to take back a move.");
+
+                state.level_message = match reason {
+                    LevelFailure::OutOfMoves => format!("Out of moves! {retry}"),
+                    LevelFailure::OutOfTime => format!("Out of time! Press {reset} to try again."),
+                    LevelFailure::OutOfSouls => {
+                        format!("You have no souls left to summon! {retry}")
+                    }
+                    LevelFailure::NoValidPlacements => {
+                        format!("There is nowhere left to summon! {retry}")
+                    }
+                };
+            }
+            SideEffect::Rejected { .. } => {
+                audio
+                    .play(audio_files.denied.clone())
+                    .with_volume(settings.sfx(DENIED_VOLUME));
+            }
             SideEffect::RemoveGameOverCondition => {
                 for entity in game_overs.iter() {
                     commands.entity(entity).despawn();
@@ -204,18 +271,98 @@ fn spawn_obstacle(commands: &mut Commands, idx: usize, piece_type: PieceType) {
 
 fn spawn_game_piece(
     commands: &mut Commands,
-    idx: usize,
-    piece_type: PieceType,
-    is_player_owned: bool,
+    vis: GamePieceVisualisation,
     despawn_at: Option<f32>,
-) {
-    let mut ent = commands.spawn(GamePieceVisualisation {
-        idx,
-        piece_type,
-        is_player_owned,
-    });
+) -> Entity {
+    let mut ent = commands.spawn(vis);
 
     if let Some(despawn_time) = despawn_at {
         ent.insert((DespawnItem { despawn_time },));
     }
+
+    ent.id()
+}
+
+/// The world position a piece sprite is drawn at for the given tile
+fn tile_position(idx: usize) -> Vec2 {
+    let (x, y) = idx_to_tile(idx);
+    tile_coords(x, y).min
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::With;
+    use chrono::NaiveDate;
+    use necromatcher_core::utils::tile_to_idx;
+
+    use crate::{
+        core::{
+            event::GameEvent,
+            state::{solver::solve, Piece, PieceType},
+            GameModeSelection,
+        },
+        testing::TestApp,
+    };
+
+    use super::GameOverDude;
+
+    fn is_game_over(app: &mut TestApp) -> bool {
+        app.app
+            .world
+            .query_filtered::<(), With<GameOverDude>>()
+            .iter(&app.app.world)
+            .next()
+            .is_some()
+    }
+
+    #[test]
+    fn test_placed_pieces_are_drawn() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Campaign);
+        let drawn = app.pieces().len();
+
+        app.send(GameEvent::PlacePlayerPiece {
+            x: 1,
+            y: 2,
+            piece_type: PieceType::Swordsman,
+        });
+        assert_eq!(
+            app.state().pieces[tile_to_idx(1, 2)],
+            Piece::Player0(PieceType::Swordsman)
+        );
+        assert_eq!(app.pieces().len(), drawn + 1);
+        app.assert_pieces_match_state();
+
+        // a rejected placement doesn't draw anything
+        app.send(GameEvent::PlacePlayerPiece {
+            x: 7,
+            y: 7,
+            piece_type: PieceType::Swordsman,
+        });
+        assert_eq!(app.pieces().len(), drawn + 1);
+    }
+
+    #[test]
+    fn test_finishing_the_daily_shows_game_over_until_reset() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Daily);
+        // a fixed date rather than today's, so the test always plays the same board
+        app.send(GameEvent::LoadDaily {
+            date: NaiveDate::from_ymd_opt(2024, 4, 10).unwrap(),
+        });
+
+        let solution = solve(app.state(), 100_000).expect("the daily can be solved");
+        for (x, y, piece_type) in solution {
+            app.send(GameEvent::PlacePlayerPiece { x, y, piece_type });
+            app.advance(1.);
+        }
+
+        assert!(app.state().is_level_over());
+        assert!(is_game_over(&mut app));
+        assert!(app.pieces().is_empty());
+
+        app.send(GameEvent::Reset);
+        assert!(!is_game_over(&mut app));
+        app.assert_pieces_match_state();
+    }
 }
@@ -0,0 +1,98 @@
+//! Bevy systems that drive the [GameState] from the game loop
+
+use bevy::prelude::*;
+use rand::thread_rng;
+
+use crate::{
+    input::{DisableInput, GamePaused},
+    settings::Settings,
+};
+
+use super::{
+    event::GameEvent,
+    state::{
+        game_event_handler::StateEventHandler, opponent::choose_move, side_effects::SideEffect,
+        GameMode, GameState, Player,
+    },
+    GameModeSelection,
+};
+
+/// How long the computer waits before making its move, so people can see what happened
+const THINK_TIME: f32 = 0.6;
+
+/// A system that listens for [GameEvent]s and uses them to mutate the state
+pub fn state_mutation(
+    mut state: ResMut<GameState>,
+    mut events: EventReader<GameEvent>,
+    mut side_effect_events: EventWriter<SideEffect>,
+) {
+    for event in events.read() {
+        match state.apply_event(*event) {
+            Ok(side_effects) => {
+                for side_effect in side_effects {
+                    side_effect_events.send(side_effect);
+                }
+            }
+            Err(reason) => {
+                side_effect_events.send(SideEffect::Rejected { reason });
+            }
+        }
+    }
+}
+
+/// A system that counts down the current level's time limit
+pub fn advance_level_timer(
+    time: Res<Time>,
+    paused: Res<GamePaused>,
+    mut state: ResMut<GameState>,
+    mut side_effect_events: EventWriter<SideEffect>,
+) {
+    if paused.0 {
+        return;
+    }
+
+    for side_effect in state.advance_time(time.delta_seconds()) {
+        side_effect_events.send(side_effect);
+    }
+}
+
+/// A system that makes the computer's move when it is [Player::Player1]'s
+/// turn in a game against the computer
+#[allow(clippy::too_many_arguments)]
+pub fn computer_opponent_turn(
+    time: Res<Time>,
+    mode: Res<GameModeSelection>,
+    settings: Res<Settings>,
+    disable_input: Res<DisableInput>,
+    paused: Res<GamePaused>,
+    state: Res<GameState>,
+    mut thinking_for: Local<f32>,
+    mut events: EventWriter<GameEvent>,
+) {
+    if !is_computer_turn(&state, *mode) || state.is_level_over() {
+        *thinking_for = 0.;
+        return;
+    }
+
+    // wait for the last move to finish animating
+    if paused.0 || disable_input.0 {
+        return;
+    }
+
+    *thinking_for += time.delta_seconds();
+    if *thinking_for < THINK_TIME {
+        return;
+    }
+    *thinking_for = 0.;
+
+    if let Some((x, y, piece_type)) = choose_move(&state, settings.opponent, &mut thread_rng()) {
+        events.send(GameEvent::PlacePlayerPiece { x, y, piece_type });
+    }
+}
+
+/// Returns true if the computer should be making the next move
+pub fn is_computer_turn(state: &GameState, selection: GameModeSelection) -> bool {
+    selection == GameModeSelection::VersusComputer
+        && state.mode == GameMode::Versus
+        && state.current_player == Player::Player1
+}
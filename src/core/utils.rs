@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+pub use necromatcher_core::utils::idx_to_tile;
+
 use super::{COLS, GRID_SIZE, ROWS};
 
 /// convert from tile x/y to a world coordinate
@@ -33,28 +35,3 @@ pub fn world_to_tile(world_pos: Vec2) -> Option<(usize, usize)> {
         Some((x, y))
     }
 }
-
-/// Converts from a tile x/y to an array index.
-/// Pretty basic but I use this in a few places so may as well consolidate it
-/// so I don't randomly mess it up
-pub fn tile_to_idx(x: usize, y: usize) -> usize {
-    if x == usize::MAX || y == usize::MAX {
-        return usize::MAX;
-    }
-
-    x + y * COLS
-}
-
-/// Converts from an array index to a tile x/y.
-/// Pretty basic but I use this in a few places so may as well consolidate it
-/// so I don't randomly mess it up
-pub fn idx_to_tile(idx: usize) -> (usize, usize) {
-    if idx == usize::MAX {
-        (idx, idx)
-    } else {
-        let x = idx % COLS;
-        let y = (idx - x) / ROWS;
-
-        (x, y)
-    }
-}
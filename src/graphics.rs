@@ -1,23 +1,30 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioControl};
 use bevy_vector_shapes::{painter::ShapePainter, shapes::RectPainter, Shape2dPlugin};
 
 use crate::{
+    audio::DESPAWN_VOLUME,
     core::{
         colours::{
-            DEFAULT_GRID_BORDER, DEFAULT_GRID_HOVER_BORDER_INVALID,
-            DEFAULT_GRID_HOVER_BORDER_VALID, PLAYER_0_COLOUR, PLAYER_1_COLOUR,
+            hover_border_colours, valid_move_overlay, DEFAULT_GRID_BORDER, MATCHING_MOVE_OVERLAY,
+            OBJECTIVE_TILE_BORDER, PREVIEW_CLEAR_BORDER, PREVIEW_CLEAR_FILL,
         },
-        state::{side_effects::GameOverDude, GameState, PieceType, PlayingPiece},
-        utils::{tile_coords, world_to_tile},
+        state::{
+            game_event_handler::FALL_DURATION,
+            objectives::{LevelObjective, ReachTile},
+            side_effects::GameOverDude,
+            GameState, PieceType, PlayingPiece,
+        },
+        utils::{idx_to_tile, tile_coords},
         COLS, GRID_SIZE, ROWS,
     },
-    input::{CursorWorldCoords, DisableInput},
+    input::{DisableInput, SelectedTile, ShowValidMoves},
     loaders::{AudioFiles, SpritesheetFiles},
+    settings::Settings,
     AppState,
 };
 
-use self::piece_visualisation::DespawnItem;
+use self::piece_visualisation::{DespawnItem, FallingPiece};
 
 pub mod piece_visualisation;
 
@@ -27,29 +34,108 @@ pub struct GraphicsPlugin;
 
 impl Plugin for GraphicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(Shape2dPlugin::default())
-            .add_systems(
-                OnEnter(AppState::Game),
-                (spawn_current_piece_icons, spawn_hover_icon_indicator),
-            )
+        app.add_plugins((Shape2dPlugin::default(), PieceVisualisationPlugin))
+            .add_systems(OnEnter(AppState::Game), spawn_hover_icon_indicator)
             .add_systems(
                 Update,
-                (
-                    draw_grid,
-                    despawn_system,
-                    update_current_piece_icon,
-                    move_hover_icon_indicator,
-                )
-                    .run_if(in_state(AppState::Game)),
+                (draw_grid, move_hover_icon_indicator).run_if(in_state(AppState::Game)),
             );
     }
 }
 
+/// Despawns and animates the pieces on the board. Kept apart from the rest of the
+/// graphics, which need a renderer, so it can run in headless tests.
+pub struct PieceVisualisationPlugin;
+
+impl Plugin for PieceVisualisationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (despawn_system, animate_falling_pieces).run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// How a grid tile is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileStyle {
+    /// Drawn inside the tile while the move overlay is shown
+    pub overlay: Option<Color>,
+    /// Fills the tile, used to ghost the pieces a placement would clear
+    pub fill: Option<Color>,
+    pub border: Color,
+}
+
+/// Works out how each tile of the grid is drawn, indexed by tile
+pub fn tile_styles(
+    state: &GameState,
+    selected: Option<(usize, usize)>,
+    piece_type: PieceType,
+    disable_input: bool,
+    show_valid_moves: bool,
+    colourblind: bool,
+) -> Vec<TileStyle> {
+    let (xsel, ysel) = selected.unwrap_or((usize::MAX, usize::MAX));
+    let preview = if disable_input {
+        None
+    } else {
+        state.preview_placement(xsel, ysel, piece_type).ok()
+    };
+    let is_valid_placement = preview.is_some();
+    let cleared = preview.map(|p| p.cleared).unwrap_or_default();
+    let show_overlay = show_valid_moves && !disable_input && state.has_capacity(piece_type);
+    let objective_tile = match state.rules.objective {
+        LevelObjective::ReachTile(ReachTile { x, y }) => Some((x, y)),
+        _ => None,
+    };
+    let (hover_valid, hover_invalid) = hover_border_colours(colourblind);
+
+    (0..ROWS * COLS)
+        .map(|idx| {
+            let (x, y) = idx_to_tile(idx);
+
+            let overlay = (show_overlay && state.is_valid_placement_position(x, y)).then(|| {
+                if state.would_match(x, y, piece_type) {
+                    MATCHING_MOVE_OVERLAY
+                } else {
+                    valid_move_overlay(colourblind)
+                }
+            });
+
+            let mut fill = None;
+            let border = if !disable_input && xsel == x && ysel == y {
+                if is_valid_placement {
+                    hover_valid
+                } else {
+                    hover_invalid
+                }
+            } else if cleared.contains(&idx) {
+                // ghost the pieces this placement would clear
+                fill = Some(PREVIEW_CLEAR_FILL);
+                PREVIEW_CLEAR_BORDER
+            } else if objective_tile == Some((x, y)) {
+                OBJECTIVE_TILE_BORDER
+            } else {
+                DEFAULT_GRID_BORDER
+            };
+
+            TileStyle {
+                overlay,
+                fill,
+                border,
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_grid(
-    cursor_coords: Res<CursorWorldCoords>,
+    selected: Res<SelectedTile>,
     current_piece: Res<PlayingPiece>,
     state: Res<GameState>,
     disable_input: Res<DisableInput>,
+    show_valid_moves: Res<ShowValidMoves>,
+    settings: Res<Settings>,
     mut painter: ShapePainter,
     game_over_query: Query<Entity, With<GameOverDude>>,
 ) {
@@ -62,91 +148,36 @@ fn draw_grid(
     painter.thickness = 0.5;
     painter.hollow = true;
 
-    let (xsel, ysel) = world_to_tile(cursor_coords.0).unwrap_or((usize::MAX, usize::MAX));
-    let is_valid_placement_position = state.is_valid_placement_position(xsel, ysel);
-    let has_capacity = state.has_capacity(current_piece.0);
+    let styles = tile_styles(
+        &state,
+        selected.0,
+        current_piece.0,
+        disable_input.0,
+        show_valid_moves.0,
+        settings.colourblind,
+    );
 
-    for x in 0..COLS {
-        for y in 0..ROWS {
-            let coords = tile_coords(x, y);
-            painter.translate(Vec3::new(coords.min.x + 1., coords.min.y + 1., 0.));
+    for (idx, style) in styles.into_iter().enumerate() {
+        let (x, y) = idx_to_tile(idx);
+        let coords = tile_coords(x, y);
+        painter.translate(Vec3::new(coords.min.x + 1., coords.min.y + 1., 0.));
 
-            if !disable_input.0 && xsel == x && ysel == y {
-                painter.color = if is_valid_placement_position && has_capacity {
-                    DEFAULT_GRID_HOVER_BORDER_VALID
-                } else {
-                    DEFAULT_GRID_HOVER_BORDER_INVALID
-                };
-            } else {
-                painter.color = DEFAULT_GRID_BORDER
-            }
+        if let Some(colour) = style.overlay {
+            painter.color = colour;
+            painter.rect(Vec2::new(GRID_SIZE as f32 - 12., GRID_SIZE as f32 - 12.));
+        }
 
+        if let Some(colour) = style.fill {
+            painter.hollow = false;
+            painter.color = colour;
             painter.rect(Vec2::new(GRID_SIZE as f32 - 2., GRID_SIZE as f32 - 2.));
-
-            painter.transform = pos;
+            painter.hollow = true;
         }
-    }
-}
-
-#[derive(Component)]
-pub struct CurrentPieceIcon(pub PieceType);
-
-fn spawn_current_piece_icons(
-    mut commands: Commands,
-    spritesheets: Res<SpritesheetFiles>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 13, 1, None, None);
-    let layout = texture_atlas_layouts.add(layout);
-
-    let window = window_query.single();
-
-    [PieceType::Bowman, PieceType::Hound, PieceType::Swordsman]
-        .iter()
-        .enumerate()
-        .for_each(|(idx, pt)| {
-            let first = match pt {
-                PieceType::Swordsman => 2,
-                PieceType::Hound => 4,
-                PieceType::Bowman => 0,
-                _ => 0,
-            };
 
-            commands.spawn((
-                SpriteSheetBundle {
-                    texture: spritesheets.main_sheet.clone(),
-                    atlas: TextureAtlas {
-                        layout: layout.clone(),
-                        index: first,
-                    },
-                    transform: Transform::from_translation(Vec3::new(
-                        -0.5 * window.width() + 2. * SHAPE_SIZE,
-                        -0.5 * window.height() + 2. * SHAPE_SIZE + idx as f32 * 3. * SHAPE_SIZE,
-                        0.0,
-                    ))
-                    .with_scale(Vec3::new(0.5, 0.5, 1.0)),
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                    ..default()
-                },
-                CurrentPieceIcon(*pt),
-            ));
-        });
-}
+        painter.color = style.border;
+        painter.rect(Vec2::new(GRID_SIZE as f32 - 2., GRID_SIZE as f32 - 2.));
 
-fn update_current_piece_icon(
-    current_piece: Res<PlayingPiece>,
-    mut icons: Query<(&mut Sprite, &CurrentPieceIcon)>,
-) {
-    for (mut icon, piece) in icons.iter_mut() {
-        icon.color = if piece.0 == current_piece.0 {
-            PLAYER_0_COLOUR
-        } else {
-            PLAYER_1_COLOUR
-        };
+        painter.transform = pos;
     }
 }
 
@@ -180,7 +211,7 @@ pub fn spawn_hover_icon_indicator(
 }
 
 pub fn move_hover_icon_indicator(
-    cursor_position: Res<CursorWorldCoords>,
+    selected: Res<SelectedTile>,
     current_piece: Res<PlayingPiece>,
     mut icons: Query<
         (&mut Visibility, &mut TextureAtlas, &mut Transform),
@@ -194,7 +225,7 @@ pub fn move_hover_icon_indicator(
         PieceType::Wall => 0,
     };
 
-    let (x, y) = world_to_tile(cursor_position.0).unwrap_or((usize::MAX, usize::MAX));
+    let (x, y) = selected.0.unwrap_or((usize::MAX, usize::MAX));
     let location = if x == usize::MAX || y == usize::MAX {
         tile_coords(0, 0)
     } else {
@@ -212,15 +243,18 @@ pub fn move_hover_icon_indicator(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn despawn_system(
     mut commands: Commands,
     time: Res<Time>,
     audio: Res<Audio>,
     audio_files: Res<AudioFiles>,
+    settings: Res<Settings>,
     mut disable_input: ResMut<DisableInput>,
     despawn_items: Query<(Entity, &DespawnItem)>,
+    falling: Query<(), With<FallingPiece>>,
 ) {
-    disable_input.0 = !despawn_items.is_empty();
+    disable_input.0 = !despawn_items.is_empty() || !falling.is_empty();
 
     let mut any_despawned = false;
 
@@ -232,6 +266,135 @@ fn despawn_system(
     }
 
     if any_despawned {
-        audio.play(audio_files.despawn.clone()).with_volume(0.1);
+        audio
+            .play(audio_files.despawn.clone())
+            .with_volume(settings.sfx(DESPAWN_VOLUME));
+    }
+}
+
+fn animate_falling_pieces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pieces: Query<(Entity, &FallingPiece, &mut Transform, &mut Visibility)>,
+) {
+    for (entity, falling, mut tx, mut visibility) in pieces.iter_mut() {
+        let progress = (time.elapsed_seconds() - falling.start_time) / FALL_DURATION;
+
+        *visibility = if falling.hidden_until_start && progress < 0. {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        // ease in so pieces speed up as they fall
+        let t = progress.clamp(0., 1.).powi(2);
+        tx.translation = falling.from.lerp(falling.to, t).extend(0.5);
+
+        if progress >= 1. {
+            commands.entity(entity).remove::<FallingPiece>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            event::GameEvent,
+            state::{preview::PlacementPreview, solver::valid_placements, GameState, PlayingPiece},
+            GameModeSelection,
+        },
+        input::{DisableInput, SelectedTile, ShowValidMoves},
+        settings::Settings,
+        testing::{snapshot::assert_snapshot, TestApp},
+    };
+
+    use super::PieceType;
+
+    /// The first summon that would make a match
+    fn matching_placement(state: &GameState) -> ((usize, usize, PieceType), PlacementPreview) {
+        valid_placements(state)
+            .into_iter()
+            .find_map(|(x, y, pt)| {
+                let preview = state.preview_placement(x, y, pt).ok()?;
+                (!preview.cleared.is_empty()).then_some(((x, y, pt), preview))
+            })
+            .expect("there is a matching move")
+    }
+
+    #[test]
+    fn test_matched_pieces_despawn_after_a_delay() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Campaign);
+
+        let ((x, y, piece_type), preview) = matching_placement(app.state());
+        app.send(GameEvent::PlacePlayerPiece { x, y, piece_type });
+        let mut cleared = preview.cleared;
+        cleared.sort();
+        assert_eq!(app.despawning(), cleared);
+
+        // input waits for the matched pieces to go
+        app.advance(0.1);
+        assert_eq!(app.despawning(), cleared);
+        assert!(app.app.world.resource::<DisableInput>().0);
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial_despawning", &snapshot);
+
+        app.advance(1.);
+        assert!(app.despawning().is_empty());
+        assert!(!app.app.world.resource::<DisableInput>().0);
+        app.assert_pieces_match_state();
+    }
+
+    #[test]
+    fn test_level_snapshots() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Campaign);
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial", &snapshot);
+
+        let mut app = TestApp::new();
+        app.app.world.resource_mut::<Settings>().colourblind = true;
+        app.start(GameModeSelection::Versus);
+        let snapshot = app.snapshot();
+        assert_snapshot("versus_colourblind", &snapshot);
+    }
+
+    #[test]
+    fn test_hover_snapshots() {
+        let mut app = TestApp::new();
+        app.start(GameModeSelection::Campaign);
+
+        // a summon that makes a match ghosts what it clears, with the move overlay on
+        let ((x, y, piece_type), _) = matching_placement(app.state());
+        app.app.insert_resource(SelectedTile(Some((x, y))));
+        app.app.insert_resource(PlayingPiece(piece_type));
+        app.app.insert_resource(ShowValidMoves(true));
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial_hover_match", &snapshot);
+
+        // nowhere near the player's pieces
+        app.app.insert_resource(SelectedTile(Some((7, 7))));
+        app.app.insert_resource(ShowValidMoves(false));
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial_hover_invalid", &snapshot);
+    }
+
+    #[test]
+    fn test_colourblind_hover_snapshots() {
+        let mut app = TestApp::new();
+        app.app.world.resource_mut::<Settings>().colourblind = true;
+        app.start(GameModeSelection::Campaign);
+
+        let ((x, y, piece_type), _) = matching_placement(app.state());
+        app.app.insert_resource(SelectedTile(Some((x, y))));
+        app.app.insert_resource(PlayingPiece(piece_type));
+        app.app.insert_resource(ShowValidMoves(true));
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial_hover_match_colourblind", &snapshot);
+
+        app.app.insert_resource(SelectedTile(Some((7, 7))));
+        let snapshot = app.snapshot();
+        assert_snapshot("tutorial_hover_invalid_colourblind", &snapshot);
     }
 }
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::core::state::PieceType;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 pub struct GamePieceVisualisation {
     pub idx: usize,
     pub piece_type: PieceType,
@@ -13,3 +13,13 @@ pub struct GamePieceVisualisation {
 pub struct DespawnItem {
     pub despawn_time: f32,
 }
+
+/// Slides a piece from one position to another, used when pieces fall
+#[derive(Component)]
+pub struct FallingPiece {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub start_time: f32,
+    /// Pieces dropping in from above the board stay hidden until they start falling
+    pub hidden_until_start: bool,
+}
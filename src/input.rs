@@ -1,17 +1,51 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem, window::PrimaryWindow};
 
 use crate::{
-    core::{event::GameEvent, state::PlayingPiece, utils::world_to_tile, MainCamera},
+    core::{
+        event::GameEvent,
+        state::{GameState, PieceType, Player, PlayingPiece},
+        systems::is_computer_turn,
+        utils::world_to_tile,
+        GameModeSelection, MainCamera, COLS, ROWS,
+    },
     AppState,
 };
+
+use self::{
+    actions::{update_action_state, Action, ActionState, CursorDirection},
+    touch::{handle_action_buttons, track_touches, TouchTracker},
+};
+
+pub mod actions;
+pub mod touch;
+
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CursorWorldCoords>()
             .init_resource::<DisableInput>()
-            .add_systems(PreUpdate, track_cursor_position)
-            .add_systems(Update, start_game.run_if(in_state(AppState::Menu)))
+            .init_resource::<ActionState>()
+            .init_resource::<GamePaused>()
+            .init_resource::<ShowValidMoves>()
+            .init_resource::<GridCursor>()
+            .init_resource::<SelectedTile>()
+            .init_resource::<TouchTracker>()
+            .add_systems(
+                PreUpdate,
+                (
+                    track_cursor_position,
+                    update_action_state,
+                    track_touches,
+                    handle_action_buttons,
+                    move_grid_cursor,
+                    update_selected_tile,
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .after(UiSystem::Focus),
+            )
+            .add_systems(Update, handle_menu_input.run_if(in_state(AppState::Menu)))
             .add_systems(Update, handle_piece_type.run_if(in_state(AppState::Game)));
     }
 }
@@ -23,18 +57,54 @@ pub struct CursorWorldCoords(pub Vec2);
 #[derive(Resource, Default)]
 pub struct DisableInput(pub bool);
 
+/// A cursor on the grid that can be moved with the keyboard or a gamepad
+/// so the game can be played without a mouse
+#[derive(Resource, Default)]
+pub struct GridCursor {
+    pub x: usize,
+    pub y: usize,
+    /// True when the grid cursor was used more recently than the mouse
+    pub active: bool,
+}
+
+/// The tile the player is pointing at, either with the mouse or the [GridCursor]
+#[derive(Resource, Default)]
+pub struct SelectedTile(pub Option<(usize, usize)>);
+
+/// True while the game is paused, which blocks any game input other than unpausing
+#[derive(Resource, Default)]
+pub struct GamePaused(pub bool);
+
+/// True while the overlay showing every tile the current creature can be summoned on is shown
+#[derive(Resource, Default)]
+pub struct ShowValidMoves(pub bool);
+
 fn track_cursor_position(
     mut cursor_coords: ResMut<CursorWorldCoords>,
+    mut grid_cursor: ResMut<GridCursor>,
+    touches: Res<Touches>,
     window_query: Query<&Window, With<PrimaryWindow>>,
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
 ) {
     let (camera, camera_transform) = camera_query.single();
     let window = window_query.single();
 
+    // touches take priority over the mouse, including the frame a touch is released
+    // so that taps are placed where the finger lifted
+    let touch_position = touches
+        .iter()
+        .chain(touches.iter_just_released())
+        .next()
+        .map(|touch| touch.position());
+
+    if touch_position.is_some() {
+        grid_cursor.active = false;
+    }
+
     // check if the cursor is inside the window and get its position
     // then, ask bevy to convert into world coordinates, and truncate to discard Z
-    if let Some(world_position) = window
-        .cursor_position()
+    if let Some(world_position) = touch_position
+        .or_else(|| window.cursor_position())
         .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
         .map(|ray| ray.origin.truncate())
     {
@@ -42,25 +112,109 @@ fn track_cursor_position(
     }
 }
 
-pub fn handle_piece_type(
+fn move_grid_cursor(
+    actions: Res<ActionState>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut grid_cursor: ResMut<GridCursor>,
+) {
+    // moving the mouse hands control back to the mouse
+    if cursor_moved.read().count() > 0 {
+        grid_cursor.active = false;
+    }
+
+    for direction in [
+        CursorDirection::Up,
+        CursorDirection::Down,
+        CursorDirection::Left,
+        CursorDirection::Right,
+    ] {
+        if !actions.just_pressed(Action::MoveCursor(direction)) {
+            continue;
+        }
+
+        // the first press just shows the cursor where it was left
+        if grid_cursor.active {
+            match direction {
+                CursorDirection::Up => grid_cursor.y = (grid_cursor.y + 1).min(ROWS - 1),
+                CursorDirection::Down => grid_cursor.y = grid_cursor.y.saturating_sub(1),
+                CursorDirection::Left => grid_cursor.x = grid_cursor.x.saturating_sub(1),
+                CursorDirection::Right => grid_cursor.x = (grid_cursor.x + 1).min(COLS - 1),
+            }
+        }
+
+        grid_cursor.active = true;
+    }
+}
+
+fn update_selected_tile(
     cursor_coords: Res<CursorWorldCoords>,
-    buttons: Res<ButtonInput<MouseButton>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    grid_cursor: Res<GridCursor>,
+    mut selected: ResMut<SelectedTile>,
+) {
+    selected.0 = if grid_cursor.active {
+        Some((grid_cursor.x, grid_cursor.y))
+    } else {
+        world_to_tile(cursor_coords.0)
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_piece_type(
+    selected: Res<SelectedTile>,
+    actions: Res<ActionState>,
     disable_input: Res<DisableInput>,
+    state: Res<GameState>,
+    mode: Res<GameModeSelection>,
+    mut paused: ResMut<GamePaused>,
+    mut show_valid_moves: ResMut<ShowValidMoves>,
     mut playing_piece: ResMut<PlayingPiece>,
     mut state_events: EventWriter<GameEvent>,
 ) {
-    if buttons.just_pressed(MouseButton::Right) || keyboard_input.just_pressed(KeyCode::KeyS) {
+    if actions.just_pressed(Action::Pause) {
+        paused.0 = !paused.0;
+    }
+
+    if paused.0 {
+        return;
+    }
+
+    if actions.just_pressed(Action::ToggleMoveOverlay) {
+        show_valid_moves.0 = !show_valid_moves.0;
+    }
+
+    if actions.just_pressed(Action::CycleSummon) {
         playing_piece.0 = playing_piece.0.toggle();
     }
 
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
+    for piece_type in [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman] {
+        if actions.just_pressed(Action::SelectSummon(piece_type)) && state.has_capacity(piece_type)
+        {
+            playing_piece.0 = piece_type;
+        }
+    }
+
+    if actions.just_pressed(Action::Reset) {
         state_events.send(GameEvent::Reset);
     }
 
-    if !disable_input.0 && buttons.just_pressed(MouseButton::Left) {
-        let (x, y) = world_to_tile(cursor_coords.0).unwrap_or((usize::MAX, usize::MAX));
-        if x < usize::MAX && y < usize::MAX {
+    if disable_input.0 || is_computer_turn(&state, *mode) {
+        return;
+    }
+
+    if actions.just_pressed(Action::Undo) {
+        // take back the computer's replies as well as the player's move, unless it
+        // missed its turn. With nothing to undo a single event shows why.
+        let undos = match *mode {
+            GameModeSelection::VersusComputer => state.undos_to_take_back(Player::Player0),
+            _ => None,
+        };
+        for _ in 0..undos.unwrap_or(1) {
+            state_events.send(GameEvent::Undo);
+        }
+    }
+
+    if actions.just_pressed(Action::Place) {
+        if let Some((x, y)) = selected.0 {
             info!(
                 "Requested piece placement at {x}, {y} - {:?}",
                 playing_piece.0
@@ -74,11 +228,28 @@ pub fn handle_piece_type(
     }
 }
 
-fn start_game(
+fn handle_menu_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    mut mode: ResMut<GameModeSelection>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if keyboard_input.just_pressed(KeyCode::Space) || actions.just_pressed(Action::Place) {
+        *mode = GameModeSelection::Campaign;
+        next_state.set(AppState::Game);
+    } else if keyboard_input.just_pressed(KeyCode::KeyE) {
+        *mode = GameModeSelection::Endless;
+        next_state.set(AppState::Game);
+    } else if keyboard_input.just_pressed(KeyCode::KeyD) {
+        *mode = GameModeSelection::Daily;
+        next_state.set(AppState::Game);
+    } else if keyboard_input.just_pressed(KeyCode::KeyV) {
+        *mode = GameModeSelection::Versus;
+        next_state.set(AppState::Game);
+    } else if keyboard_input.just_pressed(KeyCode::KeyC) {
+        *mode = GameModeSelection::VersusComputer;
         next_state.set(AppState::Game);
+    } else if keyboard_input.just_pressed(KeyCode::KeyO) {
+        next_state.set(AppState::Settings);
     }
 }
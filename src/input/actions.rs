@@ -0,0 +1,414 @@
+//! Maps raw keyboard, mouse and gamepad input to game [Action]s so that
+//! gameplay systems never need to know which device (or which key) was used.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{core::state::PieceType, settings::Settings};
+
+/// The keys that can be bound to an action. Used when saving and loading bindings
+/// as [KeyCode] doesn't implement `FromStr`.
+pub const BINDABLE_KEYS: [KeyCode; 45] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+];
+
+const BINDABLE_MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+const BINDABLE_GAMEPAD_BUTTONS: [GamepadButtonType; 16] = [
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Something the player wants to do, independent of the input device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Switch to the next summoned creature
+    CycleSummon,
+    /// Place the current creature under the cursor
+    Place,
+    /// Reset the current level
+    Reset,
+    /// Take back the last placement
+    Undo,
+    /// Pause or resume the game
+    Pause,
+    /// Move the grid cursor one tile in the given direction
+    MoveCursor(CursorDirection),
+    /// Switch directly to summoning the given creature
+    SelectSummon(PieceType),
+    /// Show or hide every tile the current creature can be summoned on
+    ToggleMoveOverlay,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::CycleSummon,
+        Action::Place,
+        Action::Reset,
+        Action::Undo,
+        Action::Pause,
+        Action::MoveCursor(CursorDirection::Up),
+        Action::MoveCursor(CursorDirection::Down),
+        Action::MoveCursor(CursorDirection::Left),
+        Action::MoveCursor(CursorDirection::Right),
+        Action::SelectSummon(PieceType::Swordsman),
+        Action::SelectSummon(PieceType::Hound),
+        Action::SelectSummon(PieceType::Bowman),
+        Action::ToggleMoveOverlay,
+    ];
+
+    /// The name used for this action in the settings file
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            Action::CycleSummon => "cycle_summon",
+            Action::Place => "place",
+            Action::Reset => "reset",
+            Action::Undo => "undo",
+            Action::Pause => "pause",
+            Action::MoveCursor(CursorDirection::Up) => "move_up",
+            Action::MoveCursor(CursorDirection::Down) => "move_down",
+            Action::MoveCursor(CursorDirection::Left) => "move_left",
+            Action::MoveCursor(CursorDirection::Right) => "move_right",
+            Action::SelectSummon(PieceType::Swordsman) => "select_swordsman",
+            Action::SelectSummon(PieceType::Hound) => "select_hound",
+            Action::SelectSummon(PieceType::Bowman) => "select_bowman",
+            Action::SelectSummon(PieceType::Wall) => "select_wall",
+            Action::ToggleMoveOverlay => "toggle_moves",
+        }
+    }
+
+    /// A human readable name for the action
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::CycleSummon => "Change summoned creature",
+            Action::Place => "Summon",
+            Action::Reset => "Reset level",
+            Action::Undo => "Undo",
+            Action::Pause => "Pause",
+            Action::MoveCursor(CursorDirection::Up) => "Move cursor up",
+            Action::MoveCursor(CursorDirection::Down) => "Move cursor down",
+            Action::MoveCursor(CursorDirection::Left) => "Move cursor left",
+            Action::MoveCursor(CursorDirection::Right) => "Move cursor right",
+            Action::SelectSummon(PieceType::Swordsman) => "Summon swordsmen",
+            Action::SelectSummon(PieceType::Hound) => "Summon hounds",
+            Action::SelectSummon(PieceType::Bowman) => "Summon bowmen",
+            Action::SelectSummon(PieceType::Wall) => "Summon walls",
+            Action::ToggleMoveOverlay => "Show valid moves",
+        }
+    }
+
+    pub fn from_config_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.config_name() == name)
+    }
+}
+
+/// A physical input that can trigger an [Action]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    /// Parses a binding from its debug representation, i.e. `Key(KeyS)`
+    pub fn parse(value: &str) -> Option<Self> {
+        BINDABLE_KEYS
+            .iter()
+            .map(|k| Binding::Key(*k))
+            .chain(BINDABLE_MOUSE_BUTTONS.iter().map(|b| Binding::Mouse(*b)))
+            .chain(
+                BINDABLE_GAMEPAD_BUTTONS
+                    .iter()
+                    .map(|b| Binding::Gamepad(*b)),
+            )
+            .find(|b| format!("{b:?}") == value)
+    }
+
+    /// A short name for showing the binding in help text
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                name.strip_prefix("Key")
+                    .or(name.strip_prefix("Digit"))
+                    .unwrap_or(&name)
+                    .to_lowercase()
+            }
+            Binding::Mouse(MouseButton::Left) => "left click".to_owned(),
+            Binding::Mouse(MouseButton::Right) => "right click".to_owned(),
+            Binding::Mouse(button) => format!("{button:?} click").to_lowercase(),
+            Binding::Gamepad(button) => format!("{button:?}"),
+        }
+    }
+}
+
+/// The table of which inputs trigger which actions. Loaded from and saved with the [Settings].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputBindings(pub Vec<(Action, Binding)>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Action::*;
+        use CursorDirection::*;
+
+        Self(vec![
+            // this used to be 's', which now moves the cursor down along with the rest of WASD
+            (CycleSummon, Binding::Key(KeyCode::KeyQ)),
+            (CycleSummon, Binding::Mouse(MouseButton::Right)),
+            (
+                CycleSummon,
+                Binding::Gamepad(GamepadButtonType::LeftTrigger),
+            ),
+            (
+                CycleSummon,
+                Binding::Gamepad(GamepadButtonType::RightTrigger),
+            ),
+            (Place, Binding::Mouse(MouseButton::Left)),
+            (Place, Binding::Key(KeyCode::Enter)),
+            (Place, Binding::Gamepad(GamepadButtonType::South)),
+            (Reset, Binding::Key(KeyCode::KeyR)),
+            (Reset, Binding::Gamepad(GamepadButtonType::North)),
+            (Undo, Binding::Key(KeyCode::KeyU)),
+            (Undo, Binding::Gamepad(GamepadButtonType::West)),
+            (Pause, Binding::Key(KeyCode::Escape)),
+            (Pause, Binding::Gamepad(GamepadButtonType::Start)),
+            (MoveCursor(Up), Binding::Key(KeyCode::ArrowUp)),
+            (MoveCursor(Up), Binding::Key(KeyCode::KeyW)),
+            (MoveCursor(Up), Binding::Gamepad(GamepadButtonType::DPadUp)),
+            (MoveCursor(Down), Binding::Key(KeyCode::ArrowDown)),
+            (MoveCursor(Down), Binding::Key(KeyCode::KeyS)),
+            (
+                MoveCursor(Down),
+                Binding::Gamepad(GamepadButtonType::DPadDown),
+            ),
+            (MoveCursor(Left), Binding::Key(KeyCode::ArrowLeft)),
+            (MoveCursor(Left), Binding::Key(KeyCode::KeyA)),
+            (
+                MoveCursor(Left),
+                Binding::Gamepad(GamepadButtonType::DPadLeft),
+            ),
+            (MoveCursor(Right), Binding::Key(KeyCode::ArrowRight)),
+            (MoveCursor(Right), Binding::Key(KeyCode::KeyD)),
+            (
+                MoveCursor(Right),
+                Binding::Gamepad(GamepadButtonType::DPadRight),
+            ),
+            (
+                SelectSummon(PieceType::Swordsman),
+                Binding::Key(KeyCode::Digit1),
+            ),
+            (
+                SelectSummon(PieceType::Hound),
+                Binding::Key(KeyCode::Digit2),
+            ),
+            (
+                SelectSummon(PieceType::Bowman),
+                Binding::Key(KeyCode::Digit3),
+            ),
+            (ToggleMoveOverlay, Binding::Key(KeyCode::KeyM)),
+            (
+                ToggleMoveOverlay,
+                Binding::Gamepad(GamepadButtonType::Select),
+            ),
+        ])
+    }
+}
+
+impl InputBindings {
+    pub fn bindings_for(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.0
+            .iter()
+            .filter(move |(a, _)| *a == action)
+            .map(|(_, b)| b)
+    }
+
+    /// Replaces any keyboard bindings for the action with the given key,
+    /// leaving mouse and gamepad bindings untouched
+    pub fn rebind_key(&mut self, action: Action, key: KeyCode) {
+        let is_key_for_action =
+            |(a, b): &(Action, Binding)| *a == action && matches!(b, Binding::Key(_));
+        let position = self
+            .0
+            .iter()
+            .position(is_key_for_action)
+            .unwrap_or(self.0.len());
+
+        self.0.retain(|item| !is_key_for_action(item));
+        self.0
+            .insert(position.min(self.0.len()), (action, Binding::Key(key)));
+    }
+
+    /// Replaces all bindings for the action
+    pub fn set_bindings(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.0.retain(|(a, _)| *a != action);
+        self.0.extend(bindings.into_iter().map(|b| (action, b)));
+    }
+
+    /// Describes the keyboard and mouse bindings for an action for help text, i.e. "'s' (or right click)"
+    pub fn describe(&self, action: Action) -> String {
+        let mut labels = self
+            .bindings_for(action)
+            .filter(|b| !matches!(b, Binding::Gamepad(_)))
+            .map(|b| match b {
+                Binding::Key(_) => format!("'{}'", b.label()),
+                _ => b.label(),
+            });
+
+        match (labels.next(), labels.next()) {
+            (Some(first), Some(second)) => format!("{first} (or {second})"),
+            (Some(first), None) => first,
+            _ => "(unbound)".to_owned(),
+        }
+    }
+}
+
+/// The actions triggered this frame
+#[derive(Resource, Default)]
+pub struct ActionState {
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Triggers an action this frame from something other than a binding, i.e. a touch
+    pub fn press(&mut self, action: Action) {
+        self.just_pressed.insert(action);
+    }
+}
+
+pub fn update_action_state(
+    settings: Res<Settings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut action_state: ResMut<ActionState>,
+) {
+    action_state.just_pressed.clear();
+
+    for (action, binding) in settings.bindings.0.iter() {
+        let pressed = match binding {
+            Binding::Key(key) => keyboard_input.just_pressed(*key),
+            Binding::Mouse(button) => mouse_input.just_pressed(*button),
+            Binding::Gamepad(button_type) => gamepads.iter().any(|gamepad| {
+                gamepad_input.just_pressed(GamepadButton::new(gamepad, *button_type))
+            }),
+        };
+
+        if pressed {
+            action_state.just_pressed.insert(*action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::input::{gamepad::GamepadButtonType, keyboard::KeyCode, mouse::MouseButton};
+
+    use super::{Action, Binding, InputBindings};
+
+    #[test]
+    fn test_binding_parse_round_trip() {
+        for binding in [
+            Binding::Key(KeyCode::KeyS),
+            Binding::Mouse(MouseButton::Right),
+            Binding::Gamepad(GamepadButtonType::DPadLeft),
+        ] {
+            assert_eq!(Binding::parse(&format!("{binding:?}")), Some(binding));
+        }
+
+        assert_eq!(Binding::parse("Key(F13)"), None);
+    }
+
+    #[test]
+    fn test_rebind_key_keeps_other_devices() {
+        let mut bindings = InputBindings::default();
+        bindings.rebind_key(Action::CycleSummon, KeyCode::KeyC);
+
+        assert_eq!(
+            bindings
+                .bindings_for(Action::CycleSummon)
+                .collect::<Vec<_>>(),
+            vec![
+                &Binding::Key(KeyCode::KeyC),
+                &Binding::Mouse(MouseButton::Right),
+                &Binding::Gamepad(GamepadButtonType::LeftTrigger),
+                &Binding::Gamepad(GamepadButtonType::RightTrigger),
+            ]
+        );
+        assert_eq!(
+            bindings.describe(Action::CycleSummon),
+            "'c' (or right click)"
+        );
+    }
+}
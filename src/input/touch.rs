@@ -0,0 +1,70 @@
+//! Turns touches into [Action]s so the game can be played on phones and tablets.
+//! A quick tap places a piece, holding a finger down cycles the summoned creature.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::actions::{Action, ActionState};
+
+/// How long a touch has to be held before it counts as a hold instead of a tap
+const HOLD_SECONDS: f32 = 0.5;
+
+/// How far (in logical pixels) a touch can move and still count as a tap
+const TAP_MAX_DISTANCE: f32 = 20.;
+
+/// An on-screen button that triggers an action when clicked or tapped
+#[derive(Component)]
+pub struct ActionButton(pub Action);
+
+/// Touches that are currently held down
+#[derive(Resource, Default)]
+pub struct TouchTracker {
+    /// The time each touch started and whether it has already triggered a hold
+    touches: HashMap<u64, (f32, bool)>,
+}
+
+pub fn track_touches(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut tracker: ResMut<TouchTracker>,
+    mut actions: ResMut<ActionState>,
+) {
+    let now = time.elapsed_seconds();
+
+    for touch in touches.iter_just_pressed() {
+        tracker.touches.insert(touch.id(), (now, false));
+    }
+
+    for touch in touches.iter() {
+        if let Some((started, held)) = tracker.touches.get_mut(&touch.id()) {
+            if !*held && now - *started >= HOLD_SECONDS {
+                *held = true;
+                actions.press(Action::CycleSummon);
+            }
+        }
+    }
+
+    for touch in touches.iter_just_released() {
+        let Some((_, held)) = tracker.touches.remove(&touch.id()) else {
+            continue;
+        };
+
+        if !held && touch.distance().length() <= TAP_MAX_DISTANCE {
+            actions.press(Action::Place);
+        }
+    }
+
+    for touch in touches.iter_just_canceled() {
+        tracker.touches.remove(&touch.id());
+    }
+}
+
+pub fn handle_action_buttons(
+    buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    mut actions: ResMut<ActionState>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            actions.press(button.0);
+        }
+    }
+}
@@ -14,6 +14,7 @@ pub struct AudioFiles {
     pub place: Handle<AudioSource>,
     pub despawn: Handle<AudioSource>,
     pub level_complete: Handle<AudioSource>,
+    pub denied: Handle<AudioSource>,
     pub music: Handle<AudioSource>,
 }
 
@@ -21,12 +22,14 @@ fn load_audio_files(mut commands: Commands, asset_server: Res<AssetServer>) {
     let place = asset_server.load("audio/place_piece.wav");
     let despawn = asset_server.load("audio/destroy.wav");
     let level_complete = asset_server.load("audio/level_complete.wav");
+    let denied = asset_server.load("audio/denied.wav");
     let music = asset_server.load("audio/music.wav");
 
     commands.insert_resource(AudioFiles {
         place,
         despawn,
         level_complete,
+        denied,
         music,
     });
 }
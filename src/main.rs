@@ -2,7 +2,8 @@ use bevy::{asset::AssetMetaCheck, log::info, prelude::*};
 
 use crate::{
     animation::animate_sprite, audio::InternalAudioPlugin, core::CorePlugin,
-    graphics::GraphicsPlugin, input::InputPlugin, loaders::LoaderPlugin, ui::UiPlugin,
+    graphics::GraphicsPlugin, input::InputPlugin, loaders::LoaderPlugin, scores::ScoresPlugin,
+    settings::Settings, settings::SettingsPlugin, ui::UiPlugin,
 };
 
 pub(crate) mod animation;
@@ -11,6 +12,10 @@ mod core;
 mod graphics;
 mod input;
 mod loaders;
+mod scores;
+mod settings;
+#[cfg(test)]
+mod testing;
 mod ui;
 
 // Use of a mod or pub mod is not actually necessary.
@@ -23,10 +28,13 @@ pub mod built_info {
 pub enum AppState {
     #[default]
     Menu,
+    Settings,
     Game,
 }
 
 fn main() {
+    let settings = Settings::load();
+
     let mut app = App::new();
     app.init_state::<AppState>()
         .insert_resource(AssetMetaCheck::Never)
@@ -34,10 +42,12 @@ fn main() {
             primary_window: Some(Window {
                 title: format!("Necromatcher v{}", built_info::PKG_VERSION),
                 resolution: (1280., 720.).into(),
+                mode: settings.window_mode(),
                 ..default()
             }),
             ..default()
         }),))
+        .insert_resource(settings)
         .add_plugins((
             CorePlugin,
             LoaderPlugin,
@@ -45,6 +55,8 @@ fn main() {
             GraphicsPlugin,
             UiPlugin,
             InternalAudioPlugin,
+            SettingsPlugin,
+            ScoresPlugin,
         ))
         .add_systems(Update, animate_sprite);
 
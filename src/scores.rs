@@ -0,0 +1,144 @@
+//! Best scores for the daily challenge, persisted to a plain `key = value` text file
+//! in the same way as the [settings](crate::settings).
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use chrono::NaiveDate;
+
+use crate::{
+    core::state::{side_effects::SideEffect, GameState},
+    AppState,
+};
+
+/// Where scores are saved to, relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+const SCORES_FILE: &str = "scores.cfg";
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BestScores::load())
+            .add_systems(Update, record_daily_result.run_if(in_state(AppState::Game)));
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct BestScores {
+    /// The fewest moves used to clear each day's challenge
+    pub daily: BTreeMap<NaiveDate, usize>,
+}
+
+impl BestScores {
+    /// The best number of moves for the given day, if it has been cleared
+    pub fn best_daily(&self, date: NaiveDate) -> Option<usize> {
+        self.daily.get(&date).copied()
+    }
+
+    /// Records a daily result, returning true if it is a new best
+    pub fn record_daily(&mut self, date: NaiveDate, moves: usize) -> bool {
+        match self.daily.get(&date) {
+            Some(best) if *best <= moves => false,
+            _ => {
+                self.daily.insert(date, moves);
+                true
+            }
+        }
+    }
+
+    /// Loads scores from disk, skipping anything that can't be parsed
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(data) = std::fs::read_to_string(SCORES_FILE) {
+            return Self::parse(&data);
+        }
+
+        Self::default()
+    }
+
+    /// Saves scores to disk. On the web this is a no-op.
+    pub fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = std::fs::write(SCORES_FILE, self.serialise()) {
+            warn!("Failed to save scores to {SCORES_FILE} - {e:?}");
+        }
+    }
+
+    pub fn parse(data: &str) -> Self {
+        let mut scores = Self::default();
+
+        for line in data.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            let date = key
+                .trim()
+                .strip_prefix("daily_")
+                .and_then(|date| date.parse::<NaiveDate>().ok());
+
+            match (date, value.trim().parse::<usize>()) {
+                (Some(date), Ok(moves)) => {
+                    scores.daily.insert(date, moves);
+                }
+                _ => warn!("Ignoring unknown score {}", line.trim()),
+            }
+        }
+
+        scores
+    }
+
+    pub fn serialise(&self) -> String {
+        self.daily
+            .iter()
+            .map(|(date, moves)| format!("daily_{date} = {moves}\n"))
+            .collect()
+    }
+}
+
+fn record_daily_result(
+    state: Res<GameState>,
+    mut side_effects: EventReader<SideEffect>,
+    mut scores: ResMut<BestScores>,
+) {
+    for side_effect in side_effects.read() {
+        let SideEffect::GameOver { .. } = side_effect else {
+            continue;
+        };
+
+        let Some(result) = state.daily_result() else {
+            continue;
+        };
+
+        info!("{}", result.share_text());
+
+        if scores.record_daily(result.date, result.moves) {
+            scores.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::BestScores;
+
+    #[test]
+    fn test_scores_keep_the_best_result() {
+        let date = NaiveDate::from_ymd_opt(2024, 4, 14).unwrap();
+        let mut scores = BestScores::default();
+
+        assert!(scores.record_daily(date, 6));
+        assert!(scores.record_daily(date, 4));
+        assert!(!scores.record_daily(date, 5));
+        assert_eq!(scores.best_daily(date), Some(4));
+
+        assert_eq!(BestScores::parse(&scores.serialise()), scores);
+        assert_eq!(
+            BestScores::parse("daily_yesterday = 3\n"),
+            BestScores::default()
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Player facing settings like volumes, the window mode, colour palette and key bindings.
+//! These are persisted to a plain `key = value` text file so they survive restarts.
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowMode},
+};
+use bevy_kira_audio::{AudioInstance, AudioTween};
+
+use crate::{
+    audio::{BackgroundMusic, MUSIC_VOLUME},
+    core::state::opponent::OpponentDifficulty,
+    input::actions::{Action, Binding, InputBindings},
+};
+
+/// Where settings are saved to, relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "settings.cfg";
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>().add_systems(
+            Update,
+            (apply_window_mode, apply_music_volume).run_if(resource_changed::<Settings>),
+        );
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Scales all other volumes, from 0 to 1
+    pub master_volume: f64,
+    /// The volume of the background music, from 0 to 1
+    pub music_volume: f64,
+    /// The volume of sound effects, from 0 to 1
+    pub sfx_volume: f64,
+    pub fullscreen: bool,
+    /// Swaps the red/green player colours for a blue/orange palette
+    pub colourblind: bool,
+    /// How hard the computer plays in versus games against the computer
+    pub opponent: OpponentDifficulty,
+    /// Which keys, mouse and gamepad buttons trigger each action
+    pub bindings: InputBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            colourblind: false,
+            opponent: OpponentDifficulty::default(),
+            bindings: InputBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The volume a sound effect should be played at given its base mix volume
+    pub fn sfx(&self, base_volume: f64) -> f64 {
+        base_volume * self.master_volume * self.sfx_volume
+    }
+
+    /// The volume the background music should be played at
+    pub fn music(&self) -> f64 {
+        MUSIC_VOLUME * self.master_volume * self.music_volume
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    /// Loads settings from disk, falling back to defaults for anything
+    /// that is missing or can't be parsed
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(data) = std::fs::read_to_string(SETTINGS_FILE) {
+            return Self::parse(&data);
+        }
+
+        Self::default()
+    }
+
+    /// Saves settings to disk. On the web this is a no-op.
+    pub fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = std::fs::write(SETTINGS_FILE, self.serialise()) {
+            warn!("Failed to save settings to {SETTINGS_FILE} - {e:?}");
+        }
+    }
+
+    pub fn parse(data: &str) -> Self {
+        let mut settings = Self::default();
+
+        for line in data.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "master_volume" => {
+                    settings.master_volume = parse_volume(value).unwrap_or(settings.master_volume)
+                }
+                "music_volume" => {
+                    settings.music_volume = parse_volume(value).unwrap_or(settings.music_volume)
+                }
+                "sfx_volume" => {
+                    settings.sfx_volume = parse_volume(value).unwrap_or(settings.sfx_volume)
+                }
+                "fullscreen" => settings.fullscreen = value == "true",
+                "colourblind" => settings.colourblind = value == "true",
+                "opponent" => {
+                    settings.opponent =
+                        OpponentDifficulty::from_name(value).unwrap_or(settings.opponent)
+                }
+                other => match other
+                    .strip_prefix("bind_")
+                    .and_then(Action::from_config_name)
+                {
+                    Some(action) => settings.bindings.set_bindings(
+                        action,
+                        value
+                            .split(',')
+                            .filter_map(|b| Binding::parse(b.trim()))
+                            .collect(),
+                    ),
+                    None => warn!("Ignoring unknown setting {other}"),
+                },
+            }
+        }
+
+        settings
+    }
+
+    pub fn serialise(&self) -> String {
+        let mut result = format!(
+            "master_volume = {}\nmusic_volume = {}\nsfx_volume = {}\nfullscreen = {}\ncolourblind = {}\nopponent = {}\n",
+            self.master_volume,
+            self.music_volume,
+            self.sfx_volume,
+            self.fullscreen,
+            self.colourblind,
+            self.opponent.name(),
+        );
+
+        for action in Action::ALL {
+            let bindings = self
+                .bindings
+                .bindings_for(action)
+                .map(|b| format!("{b:?}"))
+                .collect::<Vec<_>>();
+
+            result.push_str(&format!(
+                "bind_{} = {}\n",
+                action.config_name(),
+                bindings.join(", ")
+            ));
+        }
+
+        result
+    }
+}
+
+fn parse_volume(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().map(|v| v.clamp(0., 1.))
+}
+
+fn apply_window_mode(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    for mut window in windows.iter_mut() {
+        let mode = settings.window_mode();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+    }
+}
+
+fn apply_music_volume(
+    settings: Res<Settings>,
+    music: Option<Res<BackgroundMusic>>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Some(music) = music else {
+        return;
+    };
+
+    if let Some(instance) = instances.get_mut(&music.0) {
+        instance.set_volume(settings.music(), AudioTween::default());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::input::keyboard::KeyCode;
+
+    use crate::{
+        core::state::opponent::OpponentDifficulty,
+        input::actions::{Action, Binding, InputBindings},
+    };
+
+    use super::Settings;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut bindings = InputBindings::default();
+        bindings.rebind_key(Action::CycleSummon, KeyCode::KeyC);
+        bindings.set_bindings(Action::Reset, vec![]);
+
+        let settings = Settings {
+            master_volume: 0.5,
+            music_volume: 0.25,
+            sfx_volume: 0.,
+            fullscreen: true,
+            colourblind: true,
+            opponent: OpponentDifficulty::Lookahead,
+            bindings,
+        };
+
+        assert_eq!(Settings::parse(&settings.serialise()), settings);
+    }
+
+    #[test]
+    fn test_settings_ignore_bad_values() {
+        let settings = Settings::parse(
+            "master_volume = 3\nsfx_volume = loud\nbind_reset = Key(F13), Key(KeyX)\n",
+        );
+
+        assert_eq!(settings.master_volume, 1.);
+        assert_eq!(settings.sfx_volume, 1.);
+        assert_eq!(
+            settings
+                .bindings
+                .bindings_for(Action::Reset)
+                .collect::<Vec<_>>(),
+            vec![&Binding::Key(KeyCode::KeyX)]
+        );
+    }
+}
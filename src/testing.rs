@@ -0,0 +1,152 @@
+//! A headless version of the app for tests. It runs the game logic and piece
+//! visualisations with [MinimalPlugins], with empty handles in place of the audio
+//! and sprite assets, and time advanced by a fixed step each frame.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_kira_audio::Audio;
+
+use crate::{
+    core::{
+        event::GameEvent,
+        state::{GameState, Piece},
+        CorePlugin, GameModeSelection,
+    },
+    graphics::{
+        piece_visualisation::{DespawnItem, GamePieceVisualisation},
+        PieceVisualisationPlugin,
+    },
+    input::{DisableInput, GamePaused, SelectedTile, ShowValidMoves},
+    loaders::{AudioFiles, SpritesheetFiles},
+    settings::Settings,
+    AppState,
+};
+
+use self::snapshot::render_board;
+
+pub mod snapshot;
+
+/// How much time passes each frame
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    /// Builds the app, sitting in the menu
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_state::<AppState>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME))
+            .insert_resource(Settings::default())
+            .init_resource::<Audio>()
+            .insert_resource(AudioFiles {
+                place: Handle::default(),
+                despawn: Handle::default(),
+                level_complete: Handle::default(),
+                denied: Handle::default(),
+                music: Handle::default(),
+            })
+            .insert_resource(SpritesheetFiles {
+                main_sheet: Handle::default(),
+            })
+            .init_resource::<Assets<TextureAtlasLayout>>()
+            .init_resource::<DisableInput>()
+            .init_resource::<GamePaused>()
+            .init_resource::<SelectedTile>()
+            .init_resource::<ShowValidMoves>()
+            .add_plugins((CorePlugin, PieceVisualisationPlugin));
+        app.update();
+
+        Self { app }
+    }
+
+    /// Starts a game from the menu, and waits for the level to be drawn
+    pub fn start(&mut self, mode: GameModeSelection) {
+        self.app.insert_resource(mode);
+        self.app
+            .world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+        self.advance(0.1);
+    }
+
+    /// Sends an event and runs enough frames for it to be applied and drawn
+    pub fn send(&mut self, event: GameEvent) {
+        self.app.world.send_event(event);
+        self.app.update();
+        self.app.update();
+    }
+
+    /// Runs frames until at least the given number of seconds has passed
+    pub fn advance(&mut self, seconds: f32) {
+        let frames = (seconds / FRAME_TIME.as_secs_f32()).ceil() as usize;
+        for _ in 0..frames.max(1) {
+            self.app.update();
+        }
+    }
+
+    pub fn app_state(&self) -> AppState {
+        self.app.world.resource::<State<AppState>>().get().clone()
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.app.world.resource::<GameState>()
+    }
+
+    /// Renders the board as text, see [snapshot] for the format
+    pub fn snapshot(&mut self) -> String {
+        render_board(&mut self.app.world)
+    }
+
+    /// The visualised pieces that aren't waiting to be despawned, in tile order
+    pub fn pieces(&mut self) -> Vec<GamePieceVisualisation> {
+        let mut pieces = self
+            .app
+            .world
+            .query_filtered::<&GamePieceVisualisation, Without<DespawnItem>>()
+            .iter(&self.app.world)
+            .copied()
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|vis| vis.idx);
+        pieces
+    }
+
+    /// The visualised pieces waiting to be despawned, in tile order
+    pub fn despawning(&mut self) -> Vec<usize> {
+        let mut despawning = self
+            .app
+            .world
+            .query_filtered::<&GamePieceVisualisation, With<DespawnItem>>()
+            .iter(&self.app.world)
+            .map(|vis| vis.idx)
+            .collect::<Vec<_>>();
+        despawning.sort();
+        despawning
+    }
+
+    /// Checks what is drawn on the board matches the game state
+    pub fn assert_pieces_match_state(&mut self) {
+        let expected = self
+            .state()
+            .pieces
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, piece)| match *piece {
+                Piece::Empty => None,
+                Piece::Player0(pt) => Some((idx, pt, true)),
+                Piece::Player1(pt) | Piece::Obstacle(pt) => Some((idx, pt, false)),
+            })
+            .collect::<Vec<_>>();
+        let drawn = self
+            .pieces()
+            .into_iter()
+            .map(|vis| (vis.idx, vis.piece_type, vis.is_player_owned))
+            .collect::<Vec<_>>();
+
+        assert_eq!(drawn, expected);
+    }
+}
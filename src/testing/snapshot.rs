@@ -0,0 +1,173 @@
+//! Renders what the game draws to text, so tests can catch visual regressions
+//! without a GPU by comparing against goldens in `src/testing/snapshots`.
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to rewrite the goldens after an
+//! intended change, and check the diff.
+//!
+//! The board is drawn twice, top row first. On the left each tile shows its sprite,
+//! or `.` if it is empty:
+//!
+//! - the sprite sheet frame, `S`, `H` or `B` for the player's creatures, `s`, `h` or
+//!   `b` for red souls, `#` for walls and `?` for anything else
+//! - the tint, `g` or `r` for the green and red players, `b` or `o` for the
+//!   colourblind palette, `w` for untinted and `?` for anything else
+//! - `~` if the sprite is waiting to be despawned
+//!
+//! On the right each tile shows how the grid is drawn:
+//!
+//! - the move overlay, `v` for a valid summon (`b` in the colourblind palette) or `m`
+//!   for one that makes a match
+//! - the border, `.` by default, `+` or `x` when hovered by a valid or invalid
+//!   summon (`B` or `O` in the colourblind palette), `c` for a piece the hovered
+//!   summon would clear, `o` for an objective
+//!
+//! The grid isn't drawn at all once the game is over.
+
+use std::{env, fs, path::Path};
+
+use bevy::prelude::*;
+use necromatcher_core::utils::tile_to_idx;
+
+use crate::{
+    core::{
+        colours::{
+            COLOURBLIND_GRID_HOVER_BORDER_INVALID, COLOURBLIND_GRID_HOVER_BORDER_VALID,
+            COLOURBLIND_VALID_MOVE_OVERLAY, DEFAULT_GRID_BORDER, DEFAULT_GRID_HOVER_BORDER_INVALID,
+            DEFAULT_GRID_HOVER_BORDER_VALID, MATCHING_MOVE_OVERLAY, OBJECTIVE_TILE_BORDER,
+            PLAYER_0_COLOUR, PLAYER_0_COLOURBLIND_COLOUR, PLAYER_1_COLOUR,
+            PLAYER_1_COLOURBLIND_COLOUR, PREVIEW_CLEAR_BORDER, VALID_MOVE_OVERLAY,
+        },
+        state::{side_effects::GameOverDude, GameState, PlayingPiece},
+        COLS, ROWS,
+    },
+    graphics::{
+        piece_visualisation::{DespawnItem, GamePieceVisualisation},
+        tile_styles,
+    },
+    input::{DisableInput, SelectedTile, ShowValidMoves},
+    settings::Settings,
+};
+
+/// Renders the sprites and grid as text
+pub fn render_board(world: &mut World) -> String {
+    let mut sprites = vec![String::from(".  "); ROWS * COLS];
+    let mut query = world.query::<(
+        &GamePieceVisualisation,
+        Option<&TextureAtlas>,
+        Option<&Sprite>,
+        Has<DespawnItem>,
+    )>();
+    let mut drawn = query.iter(world).collect::<Vec<_>>();
+    // draw despawning sprites first, so a new piece on the same tile is shown over them
+    drawn.sort_by_key(|(vis, _, _, despawning)| (vis.idx, !despawning));
+    for (vis, atlas, sprite, despawning) in drawn {
+        sprites[vis.idx] = format!(
+            "{}{}{}",
+            frame_char(atlas.map(|atlas| atlas.index)),
+            tint_char(sprite.map(|sprite| sprite.color)),
+            if despawning { '~' } else { ' ' }
+        );
+    }
+
+    let is_game_over = world
+        .query_filtered::<(), With<GameOverDude>>()
+        .iter(world)
+        .next()
+        .is_some();
+    let grid = if is_game_over {
+        vec![String::from("  "); ROWS * COLS]
+    } else {
+        tile_styles(
+            world.resource::<GameState>(),
+            world.resource::<SelectedTile>().0,
+            world.resource::<PlayingPiece>().0,
+            world.resource::<DisableInput>().0,
+            world.resource::<ShowValidMoves>().0,
+            world.resource::<Settings>().colourblind,
+        )
+        .into_iter()
+        .map(|style| {
+            let overlay = match style.overlay {
+                None => '.',
+                Some(colour) if colour == VALID_MOVE_OVERLAY => 'v',
+                Some(colour) if colour == COLOURBLIND_VALID_MOVE_OVERLAY => 'b',
+                Some(colour) if colour == MATCHING_MOVE_OVERLAY => 'm',
+                Some(_) => '?',
+            };
+            let border = match style.border {
+                colour if colour == DEFAULT_GRID_BORDER => '.',
+                colour if colour == DEFAULT_GRID_HOVER_BORDER_VALID => '+',
+                colour if colour == DEFAULT_GRID_HOVER_BORDER_INVALID => 'x',
+                colour if colour == COLOURBLIND_GRID_HOVER_BORDER_VALID => 'B',
+                colour if colour == COLOURBLIND_GRID_HOVER_BORDER_INVALID => 'O',
+                colour if colour == PREVIEW_CLEAR_BORDER && style.fill.is_some() => 'c',
+                colour if colour == OBJECTIVE_TILE_BORDER => 'o',
+                _ => '?',
+            };
+            format!("{overlay}{border}")
+        })
+        .collect()
+    };
+
+    let mut rows = vec![];
+    for y in (0..ROWS).rev() {
+        let tiles = (0..COLS).map(|x| tile_to_idx(x, y));
+        let sprite_row = tiles.clone().map(|idx| sprites[idx].as_str());
+        let grid_row = tiles.map(|idx| grid[idx].as_str());
+
+        rows.push(format!(
+            "{}   {}",
+            sprite_row.collect::<String>(),
+            grid_row.collect::<Vec<_>>().join(" ")
+        ));
+    }
+
+    rows.join("\n") + "\n"
+}
+
+fn frame_char(index: Option<usize>) -> char {
+    match index {
+        Some(0 | 1) => 'B',
+        Some(2 | 3) => 'S',
+        Some(4 | 5) => 'H',
+        Some(6 | 7) => 'b',
+        Some(8 | 9) => 's',
+        Some(10 | 11) => 'h',
+        Some(12) => '#',
+        _ => '?',
+    }
+}
+
+fn tint_char(colour: Option<Color>) -> char {
+    match colour {
+        Some(colour) if colour == PLAYER_0_COLOUR => 'g',
+        Some(colour) if colour == PLAYER_1_COLOUR => 'r',
+        Some(colour) if colour == PLAYER_0_COLOURBLIND_COLOUR => 'b',
+        Some(colour) if colour == PLAYER_1_COLOURBLIND_COLOUR => 'o',
+        Some(colour) if colour == Color::WHITE => 'w',
+        _ => '?',
+    }
+}
+
+/// Compares a render against its golden, or writes the golden when `UPDATE_SNAPSHOTS` is set
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/testing/snapshots")
+        .join(format!("{name}.txt"));
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing snapshot {}, run the tests with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        actual == expected,
+        "Snapshot {name} changed, run the tests with UPDATE_SNAPSHOTS=1 if this is intended\n\
+         expected:\n{expected}\nactual:\n{actual}"
+    );
+}
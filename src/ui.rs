@@ -2,25 +2,51 @@ use bevy::prelude::*;
 
 use crate::{
     animation::{AnimationIndices, AnimationTimer},
-    core::state::{side_effects::GameOverDude, GameState, PieceType, PlayingPiece},
+    core::{
+        colours::player_colours,
+        state::{
+            daily::par_text,
+            side_effects::{GameOverDude, SideEffect},
+            versus::VersusResult,
+            GameMode, GameState, PieceType, Player, PlayingPiece,
+        },
+        systems::is_computer_turn,
+        GameModeSelection,
+    },
     graphics::SHAPE_SIZE,
+    input::{actions::Action, touch::ActionButton, GamePaused},
     loaders::SpritesheetFiles,
+    scores::BestScores,
+    settings::Settings,
     AppState,
 };
 
+use self::settings_screen::SettingsScreenPlugin;
+
+pub mod settings_screen;
+
+const BUTTON_COLOUR: Color = Color::rgb(0.08, 0.08, 0.08);
+const BUTTON_HOVER_COLOUR: Color = Color::rgb(0.18, 0.18, 0.18);
+const BUTTON_PRESSED_COLOUR: Color = Color::rgb(0.3, 0.3, 0.3);
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Menu), spawn_menu_ui)
+        app.add_plugins(SettingsScreenPlugin)
+            .add_systems(OnEnter(AppState::Menu), spawn_menu_ui)
             .add_systems(OnExit(AppState::Menu), despawn_menu_ui)
             .add_systems(OnEnter(AppState::Game), spawn_ui)
             .add_systems(
                 Update,
                 (
                     update_available_items_ui,
+                    update_current_piece_icon,
                     update_help_text,
                     update_level_header_text,
+                    update_paused_text,
+                    update_rejection_text,
+                    update_action_button_colours,
                 )
                     .run_if(in_state(AppState::Game)),
             );
@@ -30,6 +56,10 @@ impl Plugin for UiPlugin {
 #[derive(Component)]
 pub struct PieceTypeCounter(pub PieceType);
 
+/// The icon for a piece type on its summon button
+#[derive(Component)]
+pub struct CurrentPieceIcon(pub PieceType);
+
 #[derive(Component)]
 pub struct GameUi;
 
@@ -42,6 +72,16 @@ pub struct CurrentLevelText;
 #[derive(Component)]
 pub struct MenuItem;
 
+#[derive(Component)]
+pub struct PausedText;
+
+/// Explains why the last event was rejected, e.g. why a piece couldn't be placed
+#[derive(Component)]
+pub struct RejectionText;
+
+/// How long the reason for a rejected event stays on screen, in seconds
+const REJECTION_DISPLAY_TIME: f32 = 2.;
+
 fn spawn_menu_ui(
     mut commands: Commands,
     spritesheets: Res<SpritesheetFiles>,
@@ -85,7 +125,7 @@ fn spawn_menu_ui(
                 }, ..default()
             }).with_children(|parent| {
                 parent.spawn((
-                    TextBundle::from_section("A puzzle match 3 game made in about a day for Ludum Dare 55. Summon creatures to build up combinations of three or more human souls (red pieces), harvesting them for your own use.\n\nPress [space] to start.", text_style),
+                    TextBundle::from_section("A puzzle match 3 game made in about a day for Ludum Dare 55. Summon creatures to build up combinations of three or more human souls (red pieces), harvesting them for your own use.\n\nPress [space] or tap to start, [e] for endless mode, [d] for the daily challenge, [v] for a two player game, [c] to play the computer, or [o] for settings.", text_style),
                     PieceTypeCounter(PieceType::Bowman),
                 ));
             });
@@ -146,52 +186,86 @@ fn despawn_menu_ui(mut commands: Commands, menu_items: Query<Entity, With<MenuIt
     }
 }
 
-fn spawn_ui(mut commands: Commands) {
+fn spawn_ui(
+    mut commands: Commands,
+    spritesheets: Res<SpritesheetFiles>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
     let text_style = TextStyle {
         font_size: 18.,
         ..default()
     };
 
-    commands.spawn((
-        TextBundle::from_section(" ", text_style.clone()).with_style(Style {
-            position_type: PositionType::Absolute,
-            bottom: Val::Px(1. * SHAPE_SIZE),
-            left: Val::Px(4. * SHAPE_SIZE),
-            ..default()
-        }),
-        PieceTypeCounter(PieceType::Bowman),
-        GameUi,
-    ));
-    commands.spawn((
-        TextBundle::from_section(" ", text_style.clone()).with_style(Style {
-            position_type: PositionType::Absolute,
-            bottom: Val::Px(4. * SHAPE_SIZE),
-            left: Val::Px(4. * SHAPE_SIZE),
-            ..default()
-        }),
-        PieceTypeCounter(PieceType::Hound),
-        GameUi,
-    ));
-    commands.spawn((
-        TextBundle::from_section(" ", text_style.clone()).with_style(Style {
-            position_type: PositionType::Absolute,
-            bottom: Val::Px(7. * SHAPE_SIZE),
-            left: Val::Px(4. * SHAPE_SIZE),
-            ..default()
-        }),
-        PieceTypeCounter(PieceType::Swordsman),
-        GameUi,
-    ));
+    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 13, 1, None, None);
+    let layout = texture_atlas_layouts.add(layout);
 
-    commands.spawn((
-        TextBundle::from_section("Available Souls:", text_style.clone()).with_style(Style {
-            position_type: PositionType::Absolute,
-            bottom: Val::Px(10.5 * SHAPE_SIZE),
-            left: Val::Px(SHAPE_SIZE),
-            ..default()
-        }),
-        GameUi,
-    ));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(0.5 * SHAPE_SIZE),
+                    bottom: Val::Px(SHAPE_SIZE),
+                    left: Val::Px(SHAPE_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            GameUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Available Souls:",
+                text_style.clone(),
+            ));
+
+            for pt in [PieceType::Swordsman, PieceType::Hound, PieceType::Bowman] {
+                let index = match pt {
+                    PieceType::Swordsman => 2,
+                    PieceType::Hound => 4,
+                    PieceType::Bowman => 0,
+                    _ => 0,
+                };
+
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(SHAPE_SIZE),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        ActionButton(Action::SelectSummon(pt)),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            AtlasImageBundle {
+                                style: Style {
+                                    width: Val::Px(3. * SHAPE_SIZE),
+                                    height: Val::Px(3. * SHAPE_SIZE),
+                                    ..default()
+                                },
+                                image: UiImage::new(spritesheets.main_sheet.clone()),
+                                texture_atlas: TextureAtlas {
+                                    layout: layout.clone(),
+                                    index,
+                                },
+                                ..default()
+                            },
+                            CurrentPieceIcon(pt),
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(" ", text_style.clone()),
+                            PieceTypeCounter(pt),
+                            GameUi,
+                        ));
+                    });
+            }
+        });
 
     commands.spawn((
         TextBundle::from_section(" ", text_style.clone()).with_style(Style {
@@ -226,16 +300,220 @@ fn spawn_ui(mut commands: Commands) {
         GameUi,
         CurrentLevelText,
     ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(SHAPE_SIZE),
+                    bottom: Val::Px(SHAPE_SIZE),
+                    right: Val::Px(SHAPE_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            GameUi,
+        ))
+        .with_children(|parent| {
+            for (label, action) in [
+                ("Switch", Action::CycleSummon),
+                ("Undo", Action::Undo),
+                ("Reset", Action::Reset),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(12. * SHAPE_SIZE),
+                                padding: UiRect::all(Val::Px(SHAPE_SIZE)),
+                                justify_content: JustifyContent::Center,
+                                border: UiRect::all(Val::Px(1.)),
+                                ..default()
+                            },
+                            border_color: Color::DARK_GRAY.into(),
+                            background_color: BUTTON_COLOUR.into(),
+                            ..default()
+                        },
+                        ActionButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+            }
+        });
+
+    let mut rejection_style = text_style.clone();
+    rejection_style.color = Color::ORANGE_RED;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Px(SHAPE_SIZE),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(" ", rejection_style),
+                RejectionText,
+            ));
+        });
+
+    let mut paused_style = text_style.clone();
+    paused_style.font_size = 48.;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            GameUi,
+            PausedText,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Paused", paused_style));
+        });
+}
+
+fn update_action_button_colours(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), With<ActionButton>>,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        *background = match interaction {
+            Interaction::Pressed => BUTTON_PRESSED_COLOUR,
+            Interaction::Hovered => BUTTON_HOVER_COLOUR,
+            Interaction::None => BUTTON_COLOUR,
+        }
+        .into();
+    }
+}
+
+fn update_paused_text(
+    paused: Res<GamePaused>,
+    mut paused_text: Query<&mut Visibility, With<PausedText>>,
+) {
+    for mut visibility in paused_text.iter_mut() {
+        *visibility = if paused.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_rejection_text(
+    time: Res<Time>,
+    mut side_effects: EventReader<SideEffect>,
+    mut shown_at: Local<Option<f32>>,
+    mut texts: Query<&mut Text, With<RejectionText>>,
+) {
+    let now = time.elapsed_seconds();
+    let reason = side_effects
+        .read()
+        .fold(None, |last, side_effect| match side_effect {
+            SideEffect::Rejected { reason } => Some(*reason),
+            _ => last,
+        });
+
+    for mut text in texts.iter_mut() {
+        let section = &mut text.sections[0];
+
+        if let Some(reason) = reason {
+            section.value = reason.to_string();
+            *shown_at = Some(now);
+        }
+
+        // fade out over the last half of the display time
+        let shown_for = shown_at.map_or(REJECTION_DISPLAY_TIME, |at| now - at);
+        let alpha = (2. * (1. - shown_for / REJECTION_DISPLAY_TIME)).clamp(0., 1.);
+        section.style.color.set_a(alpha);
+    }
 }
 
 fn update_level_header_text(
     state: Res<GameState>,
+    scores: Res<BestScores>,
+    mode: Res<GameModeSelection>,
     game_over: Query<Entity, With<GameOverDude>>,
     mut header_text: Query<&mut Text, With<CurrentLevelText>>,
 ) {
     for mut header in header_text.iter_mut() {
+        if let Some(result) = state.daily_result().filter(|_| !game_over.is_empty()) {
+            header.sections[0].value = "Daily challenge complete!".to_owned();
+            header.sections[1].value = format!(
+                "\n{}\nYour best today is {} moves. Reset to try again.",
+                result.share_text(),
+                scores.best_daily(result.date).unwrap_or(result.moves)
+            );
+            header.sections[2].value = String::new();
+            continue;
+        }
+
+        if let Some(result) = state.versus_result().filter(|_| !game_over.is_empty()) {
+            header.sections[0].value = match (*mode, result) {
+                (_, VersusResult::Draw) => "It's a draw!".to_owned(),
+                (GameModeSelection::VersusComputer, VersusResult::Won(Player::Player0)) => {
+                    "You win!".to_owned()
+                }
+                (GameModeSelection::VersusComputer, VersusResult::Won(Player::Player1)) => {
+                    "The computer wins!".to_owned()
+                }
+                (_, VersusResult::Won(winner)) => format!("{} wins!", winner.name()),
+            };
+            header.sections[1].value = "\nPress reset to play again.".to_owned();
+            header.sections[2].value = String::new();
+            continue;
+        }
+
         header.sections[0].value = if game_over.is_empty() {
-            format!("Level {}", state.get_current_level())
+            let title = match state.mode {
+                GameMode::Campaign => format!("Level {}", state.get_current_level()),
+                GameMode::Endless { difficulty, .. } => format!(
+                    "Endless - Level {} (difficulty {difficulty})",
+                    state.get_current_level()
+                ),
+                GameMode::Daily { date, par } => format!(
+                    "Daily {date} - {} moves (par {})",
+                    state.moves_this_level(),
+                    par_text(par)
+                ),
+                GameMode::Versus if is_computer_turn(&state, *mode) => {
+                    "Versus computer - the computer is thinking".to_owned()
+                }
+                GameMode::Versus if *mode == GameModeSelection::VersusComputer => {
+                    "Versus computer - your turn".to_owned()
+                }
+                GameMode::Versus => format!("Versus - {}'s turn", state.current_player.name()),
+            };
+
+            let mut limits = String::new();
+            if let Some(moves) = state.moves_remaining() {
+                limits.push_str(&format!(" - {moves} moves left"));
+            }
+            if let Some(seconds) = state.time_remaining() {
+                limits.push_str(&format!(" - {:.0}s left", seconds.ceil()));
+            }
+
+            format!("{title}{limits}")
         } else {
             "Game Over!".to_owned()
         };
@@ -246,8 +524,8 @@ fn update_level_header_text(
             String::new()
         } else {
             format!(
-                "\n\nHarvest {} more red souls to win",
-                state.count_red_cells()
+                "\n\n{}",
+                state.rules.objective.as_objective().describe(&state)
             )
         };
     }
@@ -256,6 +534,7 @@ fn update_level_header_text(
 fn update_available_items_ui(
     state: Res<GameState>,
     current_piece: Res<PlayingPiece>,
+    settings: Res<Settings>,
     mut pieces: Query<(&mut Text, &PieceTypeCounter), With<GameUi>>,
 ) {
     for (mut text, piece) in pieces.iter_mut() {
@@ -269,24 +548,65 @@ fn update_available_items_ui(
             ),
         };
 
-        text.sections[0].value = format!("{value} remaining");
+        text.sections[0].value = format!(
+            "{} {value} remaining",
+            settings.bindings.describe(Action::SelectSummon(piece.0))
+        );
         text.sections[0].style.color = if piece.0 == current_piece.0 {
             Color::WHITE
+        } else if state.has_capacity(piece.0) {
+            Color::GRAY
         } else {
             Color::DARK_GRAY
         };
     }
 }
 
+fn update_current_piece_icon(
+    state: Res<GameState>,
+    current_piece: Res<PlayingPiece>,
+    settings: Res<Settings>,
+    mut icons: Query<(&mut BackgroundColor, &CurrentPieceIcon)>,
+) {
+    let (mut own_colour, mut other_colour) = player_colours(settings.colourblind);
+    if state.current_player == Player::Player1 {
+        std::mem::swap(&mut own_colour, &mut other_colour);
+    }
+
+    for (mut icon, piece) in icons.iter_mut() {
+        *icon = if piece.0 == current_piece.0 {
+            own_colour
+        } else if state.has_capacity(piece.0) {
+            other_colour
+        } else {
+            Color::DARK_GRAY
+        }
+        .into();
+    }
+}
+
 fn update_help_text(
     state: Res<GameState>,
+    settings: Res<Settings>,
     mut pieces: Query<&mut Text, (With<HelpText>, With<GameUi>)>,
 ) {
+    let bindings = &settings.bindings;
+
     for mut text in pieces.iter_mut() {
         text.sections[0].value = if state.is_level_over() {
-            String::from("YOU WIN!\n Hit 'r' to start again")
+            format!(
+                "YOU WIN!\n Hit {} to start again",
+                bindings.describe(Action::Reset)
+            )
         } else {
-            "Match 3 in a row to harvest\n\nHarvest all the red souls\n\nYou can only harvest next to\na green soul\n\nPress 's' to change summoned creature\n(or right click)\n\nPress 'r' to reset the level".to_owned()
+            format!(
+                "Match 3 in a row to harvest\n\nHarvest all the red souls\n\nYou can only harvest next to\na green soul\n\nPress {} to change summoned creature\n\nPress {} to show where you can summon\n\nPress {} to undo\n\nPress {} to reset the level\n\nPress {} to pause",
+                bindings.describe(Action::CycleSummon),
+                bindings.describe(Action::ToggleMoveOverlay),
+                bindings.describe(Action::Undo),
+                bindings.describe(Action::Reset),
+                bindings.describe(Action::Pause),
+            )
         };
     }
 }
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::actions::{Action, Binding, BINDABLE_KEYS},
+    settings::Settings,
+    AppState,
+};
+
+/// How much a volume changes with each left/right key press
+const VOLUME_STEP: f64 = 0.1;
+
+/// The number of rows before the key bindings, which take up one row per [Action]
+const NUM_OPTION_ROWS: usize = 6;
+
+const NUM_ROWS: usize = NUM_OPTION_ROWS + Action::ALL.len();
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsScreen>()
+            .add_systems(OnEnter(AppState::Settings), spawn_settings_ui)
+            .add_systems(OnExit(AppState::Settings), despawn_settings_ui)
+            .add_systems(
+                Update,
+                (handle_settings_input, update_settings_text)
+                    .chain()
+                    .run_if(in_state(AppState::Settings)),
+            );
+    }
+}
+
+/// Tracks which row of the settings screen is selected
+#[derive(Resource, Default)]
+pub struct SettingsScreen {
+    selected: usize,
+    /// True when the selected key binding is waiting for a new key
+    rebinding: bool,
+}
+
+#[derive(Component)]
+pub struct SettingsItem;
+
+#[derive(Component)]
+pub struct SettingsText;
+
+fn spawn_settings_ui(mut commands: Commands, mut screen: ResMut<SettingsScreen>) {
+    *screen = SettingsScreen::default();
+
+    let text_style = TextStyle {
+        font_size: 18.,
+        color: Color::GRAY,
+        ..default()
+    };
+
+    let mut header_text_style = text_style.clone();
+    header_text_style.font_size = 48.;
+    header_text_style.color = Color::WHITE;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(20.)),
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsItem,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Settings", header_text_style));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(40.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_sections(
+                            (0..NUM_ROWS).map(|_| TextSection::new("", text_style.clone())),
+                        ),
+                        SettingsText,
+                    ));
+                });
+            parent.spawn(TextBundle::from_section(
+                "[up/down] select, [left/right] change, [enter] rebind key, [esc] save and return to the menu",
+                text_style,
+            ));
+        });
+}
+
+fn despawn_settings_ui(mut commands: Commands, items: Query<Entity, With<SettingsItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
+
+fn handle_settings_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut screen: ResMut<SettingsScreen>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if screen.rebinding {
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            screen.rebinding = false;
+        } else if let Some(key) = BINDABLE_KEYS
+            .iter()
+            .find(|key| keyboard_input.just_pressed(**key))
+        {
+            if let Some(action) = selected_action(screen.selected) {
+                settings.bindings.rebind_key(action, *key);
+            }
+            screen.rebinding = false;
+        }
+
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        settings.save();
+        next_state.set(AppState::Menu);
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        screen.selected = (screen.selected + NUM_ROWS - 1) % NUM_ROWS;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        screen.selected = (screen.selected + 1) % NUM_ROWS;
+    }
+
+    let delta = if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        -VOLUME_STEP
+    } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        VOLUME_STEP
+    } else {
+        0.
+    };
+
+    if delta != 0. {
+        match screen.selected {
+            0 => settings.master_volume = step_volume(settings.master_volume, delta),
+            1 => settings.music_volume = step_volume(settings.music_volume, delta),
+            2 => settings.sfx_volume = step_volume(settings.sfx_volume, delta),
+            3 => settings.fullscreen = !settings.fullscreen,
+            4 => settings.colourblind = !settings.colourblind,
+            5 => settings.opponent = settings.opponent.next(),
+            _ => {}
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Enter) && selected_action(screen.selected).is_some() {
+        screen.rebinding = true;
+    }
+}
+
+/// The action whose key binding is shown on the given row, if any
+fn selected_action(row: usize) -> Option<Action> {
+    row.checked_sub(NUM_OPTION_ROWS)
+        .and_then(|idx| Action::ALL.get(idx).copied())
+}
+
+fn step_volume(volume: f64, delta: f64) -> f64 {
+    // round so repeated steps don't accumulate float errors
+    ((volume + delta) * 10.).round().clamp(0., 10.) / 10.
+}
+
+fn update_settings_text(
+    screen: Res<SettingsScreen>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<SettingsText>>,
+) {
+    let mut rows = vec![
+        format!("Master volume: {:.0}%", settings.master_volume * 100.),
+        format!("Music volume: {:.0}%", settings.music_volume * 100.),
+        format!("Sound effects volume: {:.0}%", settings.sfx_volume * 100.),
+        format!(
+            "Display: {}",
+            if settings.fullscreen {
+                "fullscreen"
+            } else {
+                "windowed"
+            }
+        ),
+        format!(
+            "Colourblind palette: {}",
+            if settings.colourblind { "on" } else { "off" }
+        ),
+        format!("Computer opponent: {}", settings.opponent.name()),
+    ];
+
+    for (idx, action) in Action::ALL.iter().enumerate() {
+        let key = if screen.rebinding && screen.selected == idx + NUM_OPTION_ROWS {
+            "press a key...".to_owned()
+        } else {
+            settings
+                .bindings
+                .bindings_for(*action)
+                .find_map(|b| match b {
+                    Binding::Key(key) => Some(format!("{key:?}")),
+                    _ => None,
+                })
+                .unwrap_or("(unbound)".to_owned())
+        };
+
+        rows.push(format!("{}: {key}", action.label()));
+    }
+
+    for mut text in texts.iter_mut() {
+        for (idx, row) in rows.iter().enumerate() {
+            let section = &mut text.sections[idx];
+            section.value = format!("{row}\n");
+            section.style.color = if idx == screen.selected {
+                Color::WHITE
+            } else {
+                Color::GRAY
+            };
+        }
+    }
+}